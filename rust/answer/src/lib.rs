@@ -0,0 +1,240 @@
+//! Integer answer types shared by the solvers.
+//!
+//! By default [`Answer`] and [`SignedAnswer`] behave exactly like `u64` and `i64`.
+//! With the `checked` feature every operation is overflow checked, and an overflowed answer
+//! reports [`Overflow`] instead of wrapping or panicking. With the `bigint` feature the answers
+//! are arbitrary-precision integers, for inputs that are scaled up beyond 64 bits; results that
+//! cannot be represented at all, like a negative [`Answer`] or a non-finite float, still report
+//! [`Overflow`].
+
+use std::{
+    fmt::Display,
+    iter::{Product, Sum},
+    ops::{Add, Mul, Sub},
+};
+
+#[cfg(feature = "bigint")]
+use num_traits::{CheckedAdd, CheckedMul, CheckedSub};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "arithmetic overflow")
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+// an overflowed value is stored as `None` and stays overflowed
+#[cfg(all(feature = "checked", not(feature = "bigint")))]
+macro_rules! op {
+    ($a:expr, $b:expr, $checked:ident, $op:tt) => {
+        $a.zip($b).and_then(|(a, b)| a.$checked(b))
+    };
+}
+#[cfg(feature = "bigint")]
+macro_rules! op {
+    ($a:expr, $b:expr, $checked:ident, $op:tt) => {
+        $a.zip($b).and_then(|(a, b)| a.$checked(&b))
+    };
+}
+#[cfg(not(any(feature = "checked", feature = "bigint")))]
+macro_rules! op {
+    ($a:expr, $b:expr, $checked:ident, $op:tt) => {
+        $a $op $b
+    };
+}
+
+#[cfg(feature = "bigint")]
+macro_rules! repr {
+    ($prim:ty, $big:ty) => { Option<$big> };
+}
+#[cfg(all(feature = "checked", not(feature = "bigint")))]
+macro_rules! repr {
+    ($prim:ty, $big:ty) => { Option<$prim> };
+}
+#[cfg(not(any(feature = "checked", feature = "bigint")))]
+macro_rules! repr {
+    ($prim:ty, $big:ty) => {
+        $prim
+    };
+}
+
+macro_rules! answer {
+    ($(#[$doc:meta])* $name:ident, $prim:ty, $big:ty) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct $name(repr!($prim, $big));
+
+        #[cfg(feature = "bigint")]
+        impl $name {
+            /// Returns the answer as a primitive integer, or [`Overflow`] if it does not fit.
+            pub fn get(&self) -> Result<$prim, Overflow> {
+                let value = self.0.as_ref().ok_or(Overflow)?;
+                <$prim>::try_from(value).map_err(|_| Overflow)
+            }
+        }
+
+        #[cfg(all(feature = "checked", not(feature = "bigint")))]
+        impl $name {
+            /// Returns the answer as a primitive integer, or [`Overflow`] if it does not fit.
+            pub fn get(&self) -> Result<$prim, Overflow> {
+                self.0.ok_or(Overflow)
+            }
+        }
+
+        #[cfg(not(any(feature = "checked", feature = "bigint")))]
+        impl $name {
+            /// Returns the answer as a primitive integer, or [`Overflow`] if it does not fit.
+            pub fn get(&self) -> Result<$prim, Overflow> {
+                Ok(self.0)
+            }
+        }
+
+        impl From<$prim> for $name {
+            fn from(value: $prim) -> Self {
+                #[cfg(feature = "bigint")]
+                let value = Some(value.into());
+                #[cfg(all(feature = "checked", not(feature = "bigint")))]
+                let value = Some(value);
+                Self(value)
+            }
+        }
+
+        /// Keeps an overflow detected outside of the answer types. Without `checked` or `bigint`
+        /// there is no overflowed state, so this panics like overflowing arithmetic does.
+        impl From<Result<$prim, Overflow>> for $name {
+            fn from(value: Result<$prim, Overflow>) -> Self {
+                #[cfg(any(feature = "checked", feature = "bigint"))]
+                return Self(value.ok().map(Into::into));
+                #[cfg(not(any(feature = "checked", feature = "bigint")))]
+                Self(value.expect("arithmetic overflow"))
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self.get() {
+                    Ok(x) => x.fmt(f),
+                    #[cfg(feature = "bigint")]
+                    Err(e) => match &self.0 {
+                        Some(x) => x.fmt(f),
+                        None => e.fmt(f),
+                    },
+                    #[cfg(not(feature = "bigint"))]
+                    Err(e) => e.fmt(f),
+                }
+            }
+        }
+
+        impl PartialEq<$prim> for $name {
+            fn eq(&self, other: &$prim) -> bool {
+                self.get() == Ok(*other)
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self(op!(self.0, rhs.0, checked_add, +))
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self(op!(self.0, rhs.0, checked_sub, -))
+            }
+        }
+
+        impl Mul for $name {
+            type Output = Self;
+
+            fn mul(self, rhs: Self) -> Self {
+                Self(op!(self.0, rhs.0, checked_mul, *))
+            }
+        }
+
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                iter.fold(Self::from(0), Add::add)
+            }
+        }
+
+        impl Product for $name {
+            fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+                iter.fold(Self::from(1), Mul::mul)
+            }
+        }
+    };
+}
+
+answer!(
+    /// An unsigned puzzle answer, see the crate documentation for the available modes.
+    Answer,
+    u64,
+    num_bigint::BigUint
+);
+answer!(
+    /// A signed puzzle answer, see the crate documentation for the available modes.
+    SignedAnswer,
+    i64,
+    num_bigint::BigInt
+);
+
+impl SignedAnswer {
+    /// Converts an already rounded floating point result into an answer.
+    pub fn from_f64(value: f64) -> Self {
+        #[cfg(feature = "bigint")]
+        let value = num_traits::FromPrimitive::from_f64(value);
+        #[cfg(all(feature = "checked", not(feature = "bigint")))]
+        let value = (value.is_finite() && value >= i64::MIN as f64 && value < i64::MAX as f64)
+            .then_some(value as i64);
+        #[cfg(not(any(feature = "checked", feature = "bigint")))]
+        let value = value as i64;
+        Self(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Answer, SignedAnswer};
+
+    #[test]
+    fn test_arithmetic() {
+        let x: Answer = [3, 4, 5].into_iter().map(Answer::from).product();
+        assert_eq!(x.clone() + Answer::from(40) - Answer::from(1), 99);
+        assert_eq!(SignedAnswer::from_f64(-12.0) * SignedAnswer::from(2), -24);
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn test_overflow() {
+        use super::Overflow;
+
+        let x = Answer::from(u64::MAX) * Answer::from(2) - Answer::from(u64::MAX);
+        #[cfg(feature = "bigint")]
+        assert_eq!(x, u64::MAX);
+        assert_eq!((Answer::from(1) - Answer::from(2)).get(), Err(Overflow));
+        assert_eq!(SignedAnswer::from_f64(f64::NAN).get(), Err(Overflow));
+        assert_eq!(Answer::from(Err(Overflow)).get(), Err(Overflow));
+        #[cfg(not(feature = "bigint"))]
+        {
+            assert_eq!(x.get(), Err(Overflow));
+            assert_eq!(x.to_string(), "arithmetic overflow");
+        }
+        assert_eq!(SignedAnswer::from_f64(1e30).get(), Err(Overflow));
+        assert_eq!(
+            SignedAnswer::from_f64(1e30).to_string(),
+            if cfg!(feature = "bigint") {
+                "1000000000000000019884624838656"
+            } else {
+                "arithmetic overflow"
+            }
+        );
+    }
+}
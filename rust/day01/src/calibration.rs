@@ -0,0 +1,110 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{Match, Vocabulary};
+
+/// The digits found in a single line of a calibration document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration {
+    /// 1-based line number.
+    pub line: usize,
+    pub first: Match,
+    pub last: Match,
+}
+
+impl Calibration {
+    pub fn value(&self) -> u32 {
+        self.first.value * 10 + self.last.value
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationError {
+    NoDigit { line: usize },
+}
+
+impl Display for CalibrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CalibrationError::NoDigit { line } => write!(f, "line {} contains no digit", line),
+        }
+    }
+}
+
+impl std::error::Error for CalibrationError {}
+
+/// What to do with lines that contain no digit at all. The solvers skip them by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoDigitPolicy {
+    /// Leave the line out.
+    #[default]
+    Skip,
+    /// Abort with a [`CalibrationError`].
+    Error,
+    /// Use a calibration value of 0 for the line.
+    Zero,
+}
+
+impl NoDigitPolicy {
+    /// Returns the calibration value of a line, or `None` if the line is skipped.
+    pub fn apply(
+        self,
+        calibration: Result<Calibration, CalibrationError>,
+    ) -> Option<Result<u32, CalibrationError>> {
+        match (calibration, self) {
+            (Ok(calibration), _) => Some(Ok(calibration.value())),
+            (Err(_), NoDigitPolicy::Skip) => None,
+            (Err(e), NoDigitPolicy::Error) => Some(Err(e)),
+            (Err(_), NoDigitPolicy::Zero) => Some(Ok(0)),
+        }
+    }
+}
+
+impl FromStr for NoDigitPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(NoDigitPolicy::Skip),
+            "error" => Ok(NoDigitPolicy::Error),
+            "zero" => Ok(NoDigitPolicy::Zero),
+            _ => Err(format!(
+                "invalid policy {}, expected skip, error or zero",
+                s
+            )),
+        }
+    }
+}
+
+impl Vocabulary {
+    /// Calibrates line number `line` (1-based), reporting where its first and last digit are.
+    pub fn calibrate(&self, line: usize, input: &str) -> Result<Calibration, CalibrationError> {
+        self.first_and_last(input)
+            .map(|(first, last)| Calibration { line, first, last })
+            .ok_or(CalibrationError::NoDigit { line })
+    }
+
+    /// Calibrates every non-empty line of `input`.
+    pub fn calibrate_lines<'a>(
+        &'a self,
+        input: &'a str,
+    ) -> impl Iterator<Item = Result<Calibration, CalibrationError>> + 'a {
+        input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(i, line)| self.calibrate(i + 1, line))
+    }
+
+    /// Sums the calibration values of all lines of `input`, handling lines without digits
+    /// according to `policy`.
+    pub fn calibration_sum(
+        &self,
+        input: &str,
+        policy: NoDigitPolicy,
+    ) -> Result<u64, CalibrationError> {
+        self.calibrate_lines(input)
+            .filter_map(|c| policy.apply(c))
+            .map(|v| v.map(u64::from))
+            .sum()
+    }
+}
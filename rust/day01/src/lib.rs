@@ -1,62 +1,66 @@
+use std::io::BufRead;
+
 use aoc_traits::AdventOfCodeDay;
 
-use nom::{
-    branch::alt,
-    bytes::complete::tag,
-    character::complete::anychar,
-    combinator::{all_consuming, opt, peek, value},
-    multi::separated_list1,
-    IResult,
-};
+use color_eyre::Result;
 
-fn solve_stage1(input: &str) -> u32 {
-    input
-        .lines()
-        .map(|s| {
-            s.chars().find_map(|c| c.to_digit(10)).unwrap() * 10
-                + s.chars().rev().find_map(|c| c.to_digit(10)).unwrap()
-        })
-        .sum()
+mod calibration;
+mod vocabulary;
+pub use calibration::{Calibration, CalibrationError, NoDigitPolicy};
+pub use vocabulary::{Match, Vocabulary};
+
+/// Solves both stages, handling lines without digits according to `policy`, separately for each
+/// stage.
+pub fn solve(input: &str, policy: NoDigitPolicy) -> Result<(u64, u64), CalibrationError> {
+    Ok((
+        Vocabulary::digits().calibration_sum(input, policy)?,
+        // "oneight" counts as both 1 and 8, so the vocabulary has to report overlapping matches
+        Vocabulary::english().calibration_sum(input, policy)?,
+    ))
+}
+
+// lines without digits are skipped, use `solve` or `solve_streaming` to pick another policy
+fn solve_stage1(input: &str) -> u64 {
+    let vocabulary = Vocabulary::digits();
+    vocabulary
+        .calibration_sum(input, NoDigitPolicy::default())
+        .expect("skipped lines cannot fail")
 }
 
-// TBH this solution is a bit of a mess since the spec is super unclear.
-// It seems that actually "oneight" should be both 1 and 8 and not just 1
-// Since I first wanted to do this with nom to get used to it again, I stuck with it
-// Therefore we actually just peek and try to parse, then skip a char
-// It seems the whole task is not really suited to nom
-// a better idea would probably to use a kind of match tree, but on byte strings
-// and just look for the first and last one
-fn parse_stage2_line(input: &str) -> u32 {
-    let x: IResult<_, Vec<Option<u32>>> = all_consuming(separated_list1(
-        anychar,
-        opt(peek(alt((
-            value(1, alt((tag("one"), tag("1")))),
-            value(2, alt((tag("two"), tag("2")))),
-            value(3, alt((tag("three"), tag("3")))),
-            value(4, alt((tag("four"), tag("4")))),
-            value(5, alt((tag("five"), tag("5")))),
-            value(6, alt((tag("six"), tag("6")))),
-            value(7, alt((tag("seven"), tag("7")))),
-            value(8, alt((tag("eight"), tag("8")))),
-            value(9, alt((tag("nine"), tag("9")))),
-        )))),
-    ))(input);
-    let (_, a) = x.unwrap();
-
-    a.iter().find(|x| x.is_some()).unwrap().unwrap() * 10
-        + a.iter().rev().find(|x| x.is_some()).unwrap().unwrap()
+fn solve_stage2(input: &str) -> u64 {
+    let vocabulary = Vocabulary::english();
+    vocabulary
+        .calibration_sum(input, NoDigitPolicy::default())
+        .expect("skipped lines cannot fail")
 }
 
-fn solve_stage2(input: &str) -> u32 {
-    input.lines().map(parse_stage2_line).sum()
+/// Solves both stages while reading `input` one line at a time, holding only the current line
+/// besides the two vocabularies. Lines without digits are handled according to `policy`,
+/// separately for each stage.
+pub fn solve_streaming<R: BufRead>(input: R, policy: NoDigitPolicy) -> Result<(u64, u64)> {
+    let (digits, english) = (Vocabulary::digits(), Vocabulary::english());
+    input
+        .lines()
+        .enumerate()
+        .try_fold((0, 0), |(stage1, stage2), (i, line)| {
+            let line = line?;
+            if line.is_empty() {
+                return Ok((stage1, stage2));
+            }
+            let calibrate = |vocabulary: &Vocabulary| {
+                let value = policy.apply(vocabulary.calibrate(i + 1, &line));
+                value.transpose().map(|v| u64::from(v.unwrap_or(0)))
+            };
+            Ok((stage1 + calibrate(&digits)?, stage2 + calibrate(&english)?))
+        })
 }
 
 #[derive(Default)]
 pub struct Day1Solver;
 impl<'a> AdventOfCodeDay<'a> for Day1Solver {
     type ParsedInput = &'a str;
-    type Part1Output = u32;
-    type Part2Output = u32;
+    type Part1Output = u64;
+    type Part2Output = u64;
 
     fn parse_input(input: &str) -> &str {
         input
@@ -72,6 +76,7 @@ impl<'a> AdventOfCodeDay<'a> for Day1Solver {
 
 #[cfg(test)]
 mod tests {
+    use crate::{CalibrationError, Match, NoDigitPolicy, Vocabulary};
 
     #[test]
     fn test_stage1() {
@@ -92,4 +97,96 @@ zoneight234
 7pqrstsixteen";
         assert_eq!(super::solve_stage2(TEST_INPUT), 281);
     }
+    #[test]
+    fn test_streaming() {
+        const TEST_INPUT: &str = "1abc2
+pqr3stu8vwx
+a1b2c3d4e5f
+treb7uchet
+";
+        assert_eq!(
+            super::solve_streaming(TEST_INPUT.as_bytes(), NoDigitPolicy::Error).unwrap(),
+            (142, 142)
+        );
+    }
+    #[test]
+    fn test_vocabulary() {
+        let english = Vocabulary::english();
+        assert_eq!(english.calibration_value("zoneight"), Some(18));
+        assert_eq!(english.calibration_value("nothing"), None);
+        assert_eq!(Vocabulary::digits().calibration_value("two1nine"), Some(11));
+
+        let german = Vocabulary::with_number_words([
+            "eins", "zwei", "drei", "vier", "fuenf", "sechs", "sieben", "acht", "neun",
+        ]);
+        assert_eq!(german.calibration_value("xzweinsiebenachtx"), Some(28));
+
+        let roman = Vocabulary::new([("I", 1), ("V", 5), ("IV", 4), ("IX", 9)]);
+        let matches: Vec<_> = roman.matches("XIV").map(|m| (m.start, m.value)).collect();
+        assert_eq!(matches, [(1, 1), (1, 4), (2, 5)]);
+        assert_eq!(roman.calibration_value("XIV"), Some(15));
+    }
+    #[test]
+    #[should_panic(expected = "token values must be digits")]
+    fn test_vocabulary_value() {
+        Vocabulary::new([("ten", 10)]);
+    }
+    #[test]
+    fn test_no_digit_policy() {
+        const TEST_INPUT: &str = "1abc2
+no digits here
+treb7uchet";
+        let digits = Vocabulary::digits();
+        let calibrations: Vec<_> = digits.calibrate_lines(TEST_INPUT).collect();
+        assert_eq!(
+            calibrations[2].unwrap().first,
+            Match {
+                start: 4,
+                end: 5,
+                value: 7
+            }
+        );
+        assert_eq!(calibrations[1], Err(CalibrationError::NoDigit { line: 2 }));
+
+        assert_eq!(
+            digits.calibration_sum(TEST_INPUT, NoDigitPolicy::Skip),
+            Ok(89)
+        );
+        assert_eq!(
+            digits.calibration_sum(TEST_INPUT, NoDigitPolicy::Zero),
+            Ok(89)
+        );
+        assert_eq!(
+            digits.calibration_sum(TEST_INPUT, NoDigitPolicy::Error),
+            Err(CalibrationError::NoDigit { line: 2 })
+        );
+        assert_eq!(
+            super::solve_streaming(TEST_INPUT.as_bytes(), NoDigitPolicy::Skip).unwrap(),
+            (89, 89)
+        );
+        assert!(super::solve_streaming(TEST_INPUT.as_bytes(), NoDigitPolicy::Error).is_err());
+        assert_eq!(super::solve_stage1(TEST_INPUT), 89);
+        assert_eq!(
+            super::solve_streaming(TEST_INPUT.as_bytes(), NoDigitPolicy::default()).unwrap(),
+            (
+                super::solve_stage1(TEST_INPUT),
+                super::solve_stage2(TEST_INPUT)
+            )
+        );
+        assert_eq!(
+            super::solve(TEST_INPUT, NoDigitPolicy::Error),
+            Err(CalibrationError::NoDigit { line: 2 })
+        );
+        assert_eq!(super::solve(TEST_INPUT, NoDigitPolicy::Zero), Ok((89, 89)));
+
+        // blank lines are not lines without digits
+        assert_eq!(
+            super::solve_streaming("1abc2\n\n".as_bytes(), NoDigitPolicy::Error).unwrap(),
+            (12, 12)
+        );
+        assert_eq!(
+            digits.calibration_sum("1abc2\n\n", NoDigitPolicy::Error),
+            Ok(12)
+        );
+    }
 }
@@ -0,0 +1,146 @@
+use std::collections::{HashMap, VecDeque};
+
+/// A token of a [`Vocabulary`] found in a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    /// Byte offset of the first byte of the token.
+    pub start: usize,
+    /// Byte offset one past the last byte of the token.
+    pub end: usize,
+    pub value: u32,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    next: HashMap<u8, usize>,
+    fail: usize,
+    /// Tokens ending in this node, including the ones reachable via failure links.
+    output: Vec<usize>,
+}
+
+/// A set of tokens mapped to digits, matched with an Aho-Corasick automaton.
+///
+/// All tokens are found in a single pass, including overlapping ones, so `oneight` yields both
+/// `one` and `eight`.
+#[derive(Debug)]
+pub struct Vocabulary {
+    nodes: Vec<Node>,
+    tokens: Vec<(usize, u32)>,
+}
+
+impl Vocabulary {
+    /// Builds a vocabulary from arbitrary `(token, digit)` pairs. Calibration values are made of
+    /// two digits, so every value has to be in `0..=9`.
+    pub fn new<S: AsRef<str>>(tokens: impl IntoIterator<Item = (S, u32)>) -> Vocabulary {
+        let mut nodes = vec![Node::default()];
+        let mut lengths = Vec::new();
+        for (token, value) in tokens {
+            let token = token.as_ref().as_bytes();
+            assert!(!token.is_empty(), "tokens must not be empty");
+            assert!(value <= 9, "token values must be digits, got {}", value);
+            let mut state = 0;
+            for &b in token {
+                state = match nodes[state].next.get(&b) {
+                    Some(&next) => next,
+                    None => {
+                        nodes.push(Node::default());
+                        let next = nodes.len() - 1;
+                        nodes[state].next.insert(b, next);
+                        next
+                    }
+                };
+            }
+            nodes[state].output.push(lengths.len());
+            lengths.push((token.len(), value));
+        }
+
+        // breadth first, so the failure target of a node is always finished before the node
+        let mut queue: VecDeque<usize> = nodes[0].next.values().copied().collect();
+        while let Some(state) = queue.pop_front() {
+            let edges: Vec<_> = nodes[state].next.iter().map(|(&b, &n)| (b, n)).collect();
+            for (b, next) in edges {
+                let mut fail = nodes[state].fail;
+                let fail = loop {
+                    if let Some(&target) = nodes[fail].next.get(&b) {
+                        break target;
+                    }
+                    if fail == 0 {
+                        break 0;
+                    }
+                    fail = nodes[fail].fail;
+                };
+                nodes[next].fail = fail;
+                let inherited = nodes[fail].output.clone();
+                nodes[next].output.extend(inherited);
+                queue.push_back(next);
+            }
+        }
+
+        Vocabulary {
+            nodes,
+            tokens: lengths,
+        }
+    }
+
+    /// Only the digits `1` to `9`, as used in stage 1.
+    pub fn digits() -> Vocabulary {
+        Vocabulary::new((1..=9).map(|d| (d.to_string(), d)))
+    }
+
+    /// The digits `1` to `9` plus a word for each of them, given in order.
+    pub fn with_number_words(words: [&str; 9]) -> Vocabulary {
+        Vocabulary::new(
+            (1..=9)
+                .map(|d| (d.to_string(), d))
+                .chain(words.iter().zip(1..).map(|(w, d)| (w.to_string(), d))),
+        )
+    }
+
+    /// The digits plus the English words `one` to `nine`, as used in stage 2.
+    pub fn english() -> Vocabulary {
+        Vocabulary::with_number_words([
+            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ])
+    }
+
+    /// Returns all tokens in `line`, including overlapping ones, ordered by their end.
+    pub fn matches<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Match> + 'a {
+        let mut state = 0;
+        line.bytes().enumerate().flat_map(move |(i, b)| {
+            state = loop {
+                if let Some(&next) = self.nodes[state].next.get(&b) {
+                    break next;
+                }
+                if state == 0 {
+                    break 0;
+                }
+                state = self.nodes[state].fail;
+            };
+            self.nodes[state].output.iter().map(move |&token| {
+                let (len, value) = self.tokens[token];
+                Match {
+                    start: i + 1 - len,
+                    end: i + 1,
+                    value,
+                }
+            })
+        })
+    }
+
+    /// Returns the first and the last token of `line`, by their start position.
+    pub fn first_and_last(&self, line: &str) -> Option<(Match, Match)> {
+        self.matches(line).fold(None, |acc, m| match acc {
+            None => Some((m, m)),
+            Some((first, last)) => Some((
+                if m.start < first.start { m } else { first },
+                if m.start > last.start { m } else { last },
+            )),
+        })
+    }
+
+    /// Returns the calibration value of `line`, the first digit followed by the last one.
+    pub fn calibration_value(&self, line: &str) -> Option<u32> {
+        self.first_and_last(line)
+            .map(|(first, last)| first.value * 10 + last.value)
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+
+use crate::{Cubes, Game};
+
+/// `ln(n choose k)`, or negative infinity if `k > n`.
+fn ln_choose(n: u32, k: u32) -> f64 {
+    if k > n {
+        return f64::NEG_INFINITY;
+    }
+    let k = k.min(n - k);
+    (0..k)
+        .map(|i| f64::from(n - i).ln() - f64::from(i + 1).ln())
+        .sum()
+}
+
+/// Log-probability of drawing exactly the cubes of `reveal` when grabbing that many cubes from
+/// `bag` without replacement.
+pub fn reveal_log_probability(bag: &Cubes, reveal: &Cubes) -> f64 {
+    let total: u32 = bag.0.values().sum();
+    let drawn: u32 = reveal.0.values().sum();
+    let favourable: f64 = reveal
+        .0
+        .iter()
+        .map(|(colour, &n)| ln_choose(bag.get(colour), n))
+        .sum();
+    favourable - ln_choose(total, drawn)
+}
+
+/// Log-likelihood of all reveals of `games` under `bag`. The cubes are put back into the bag
+/// after every reveal, so the reveals are independent of each other.
+pub fn log_likelihood(games: &[Game], bag: &Cubes) -> f64 {
+    games
+        .iter()
+        .flat_map(|g| &g.cube_subsets)
+        .map(|reveal| reveal_log_probability(bag, reveal))
+        .sum()
+}
+
+/// Searches all bags holding at most `max_total` cubes for the one that makes the reveals of
+/// `games` most likely, returning it along with its log-likelihood.
+///
+/// Only the colours seen in `games` are considered, an additional colour can only lower the
+/// likelihood. Returns `None` if no bag of that size can produce the games.
+pub fn most_likely_bag<'a>(games: &[Game<'a>], max_total: u32) -> Option<(Cubes<'a>, f64)> {
+    let min_cubes =
+        games
+            .iter()
+            .map(|g| g.get_min_cubes())
+            .fold(BTreeMap::new(), |mut acc, cubes| {
+                for (colour, n) in cubes.0 {
+                    let max = acc.entry(colour).or_default();
+                    *max = n.max(*max);
+                }
+                acc
+            });
+    let (colours, mins): (Vec<_>, Vec<_>) = min_cubes.into_iter().unzip();
+
+    let mut best: Option<(Vec<u32>, f64)> = None;
+    let mut counts = mins.clone();
+    let spare = max_total.checked_sub(mins.iter().sum())?;
+    search(&mut counts, 0, spare, &mut |counts| {
+        let bag = colours
+            .iter()
+            .copied()
+            .zip(counts.iter().copied())
+            .collect();
+        let ll = log_likelihood(games, &bag);
+        if best.as_ref().is_none_or(|(_, best)| ll > *best) {
+            best = Some((counts.to_vec(), ll));
+        }
+    });
+    best.map(|(counts, ll)| (colours.into_iter().zip(counts).collect(), ll))
+}
+
+/// Calls `visit` for every way of distributing at most `spare` extra cubes over
+/// `counts[colour..]`.
+fn search(counts: &mut [u32], colour: usize, spare: u32, visit: &mut impl FnMut(&[u32])) {
+    if colour == counts.len() {
+        visit(counts);
+        return;
+    }
+    let min = counts[colour];
+    for extra in 0..=spare {
+        counts[colour] = min + extra;
+        search(counts, colour + 1, spare - extra, visit);
+    }
+    counts[colour] = min;
+}
@@ -1,87 +1,109 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::BufRead,
+};
+
 use aoc_traits::AdventOfCodeDay;
 use color_eyre::Result;
 use nom::{
-    branch::alt,
     bytes::complete::tag,
-    character::complete::{digit1, line_ending, space1},
+    character::complete::{alpha1, digit1, line_ending, space1},
     combinator::{all_consuming, map_res},
     multi::separated_list1,
     sequence::separated_pair,
     IResult,
 };
 
-#[derive(Clone)]
-struct Cubes {
-    red: u32,
-    green: u32,
-    blue: u32,
+mod inference;
+pub use inference::{log_likelihood, most_likely_bag, reveal_log_probability};
+
+/// A number of cubes per colour, colours that are not mentioned count as zero cubes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cubes<'a>(BTreeMap<&'a str, u32>);
+
+impl<'a> Cubes<'a> {
+    /// The bag of stage 1, with 12 red, 13 green and 14 blue cubes.
+    pub fn standard_bag() -> Cubes<'static> {
+        Cubes::from_iter([("red", 12), ("green", 13), ("blue", 14)])
+    }
+    pub fn get(&self, colour: &str) -> u32 {
+        self.0.get(colour).copied().unwrap_or_default()
+    }
+    pub fn colours(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.0.keys().copied()
+    }
+    /// Product of the cube counts of all `colours`.
+    pub fn power<'c>(&self, colours: impl IntoIterator<Item = &'c str>) -> u64 {
+        colours
+            .into_iter()
+            .map(|colour| u64::from(self.get(colour)))
+            .product()
+    }
 }
-impl Cubes {
-    fn power(&self) -> u32 {
-        self.red * self.green * self.blue
+
+impl<'a> FromIterator<(&'a str, u32)> for Cubes<'a> {
+    fn from_iter<T: IntoIterator<Item = (&'a str, u32)>>(iter: T) -> Self {
+        let mut cubes = Cubes::default();
+        for (colour, count) in iter {
+            *cubes.0.entry(colour).or_default() += count;
+        }
+        cubes
     }
 }
 
-#[derive(Clone)]
-pub struct Game {
+#[derive(Debug, Clone)]
+pub struct Game<'a> {
     id: u32,
-    cube_subsets: Vec<Cubes>,
+    cube_subsets: Vec<Cubes<'a>>,
 }
-impl Game {
+impl<'a> Game<'a> {
     fn is_producable_by(&self, total_cubes: &Cubes) -> bool {
-        self.cube_subsets.iter().all(|o| {
-            o.red <= total_cubes.red && o.blue <= total_cubes.blue && o.green <= total_cubes.green
-        })
-    }
-    fn get_min_cubes(&self) -> Cubes {
-        self.cube_subsets.iter().fold(
-            Cubes {
-                red: u32::MIN,
-                green: u32::MIN,
-                blue: u32::MIN,
-            },
-            |acc, o| Cubes {
-                red: acc.red.max(o.red),
-                green: acc.green.max(o.green),
-                blue: acc.blue.max(o.blue),
-            },
-        )
+        self.cube_subsets
+            .iter()
+            .all(|o| o.0.iter().all(|(colour, &n)| n <= total_cubes.get(colour)))
+    }
+    fn get_min_cubes(&self) -> Cubes<'a> {
+        self.cube_subsets
+            .iter()
+            .fold(Cubes::default(), |mut acc, o| {
+                for (&colour, &n) in &o.0 {
+                    let max = acc.0.entry(colour).or_default();
+                    *max = (*max).max(n);
+                }
+                acc
+            })
     }
 }
 
-fn parse_cubes(input: &str) -> IResult<&str, Cubes> {
+/// All games of the input, along with every colour that appears in any of them.
+#[derive(Debug, Clone)]
+pub struct Games<'a> {
+    colours: BTreeSet<&'a str>,
+    games: Vec<Game<'a>>,
+}
+
+impl<'a> Games<'a> {
+    pub fn games(&self) -> &[Game<'a>] {
+        &self.games
+    }
+    /// Every colour that appears in any game, in alphabetical order.
+    pub fn colours(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.colours.iter().copied()
+    }
+}
+
+fn parse_cubes(input: &str) -> IResult<&str, Cubes<'_>> {
     let (input, cube) = separated_list1(
         tag(", "),
-        separated_pair(
-            map_res(digit1, str::parse::<u32>),
-            space1,
-            alt((tag("red"), tag("blue"), tag("green"))),
-        ),
+        separated_pair(map_res(digit1, str::parse::<u32>), space1, alpha1),
     )(input)?;
-
     Ok((
         input,
-        Cubes {
-            red: cube
-                .iter()
-                .find(|(_, color)| color == &"red")
-                .map(|x| x.0)
-                .unwrap_or_default(),
-            green: cube
-                .iter()
-                .find(|(_, color)| color == &"green")
-                .map(|x| x.0)
-                .unwrap_or_default(),
-            blue: cube
-                .iter()
-                .find(|(_, color)| color == &"blue")
-                .map(|x| x.0)
-                .unwrap_or_default(),
-        },
+        cube.into_iter().map(|(n, colour)| (colour, n)).collect(),
     ))
 }
 
-fn parse_game(input: &str) -> IResult<&str, Game> {
+fn parse_game(input: &str) -> IResult<&str, Game<'_>> {
     let (input, _) = tag("Game ")(input)?;
     let (input, id) = map_res(digit1, str::parse::<u32>)(input)?;
     let (input, _) = tag(": ")(input)?;
@@ -89,55 +111,87 @@ fn parse_game(input: &str) -> IResult<&str, Game> {
     Ok((input, Game { id, cube_subsets }))
 }
 
-fn parse_games(input: &str) -> Result<Vec<Game>> {
+/// Parses a whole game log, one game per line.
+pub fn parse_games(input: &str) -> Result<Games<'_>> {
     let (_, games) = all_consuming(separated_list1(line_ending, parse_game))(input)
         .map_err(|e| color_eyre::eyre::eyre!("Failed to parse input: {}", e))?;
-    Ok(games)
+    let colours = games
+        .iter()
+        .flat_map(|g| g.cube_subsets.iter().flat_map(|c| c.colours()))
+        .collect();
+    Ok(Games { colours, games })
 }
 
-fn solve_stage1(games: &[Game]) -> u32 {
-    let total_cubes = Cubes {
-        red: 12,
-        green: 13,
-        blue: 14,
-    };
+fn solve_stage1(games: &Games, bag: &Cubes) -> u64 {
     games
+        .games
         .iter()
-        .filter(|g| g.is_producable_by(&total_cubes))
-        .map(|g| g.id)
-        .sum::<u32>()
+        .filter(|g| g.is_producable_by(bag))
+        .map(|g| u64::from(g.id))
+        .sum::<u64>()
 }
-fn solve_stage2(games: &[Game]) -> u32 {
+fn solve_stage2(games: &Games) -> u64 {
     games
+        .games
         .iter()
         .map(|g| g.get_min_cubes())
-        .map(|c| c.power())
-        .sum::<u32>()
+        .map(|c| c.power(games.colours.iter().copied()))
+        .sum::<u64>()
+}
+
+/// Solves both stages while reading `input` one game per line, keeping only one running sum
+/// per distinct set of colours a game shows.
+///
+/// The colours of the whole log are only known at the end, and a game lacking any of them has
+/// a power of 0. So the powers are summed per colour set, and only the sum for the games that
+/// show every colour counts.
+pub fn solve_streaming<R: BufRead>(input: R, bag: &Cubes) -> Result<(u64, u64)> {
+    let mut powers: BTreeMap<BTreeSet<String>, u64> = BTreeMap::new();
+    let mut stage1 = 0;
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let (_, game) = all_consuming(parse_game)(&line)
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to parse line {}: {}", i + 1, e))?;
+        if game.is_producable_by(bag) {
+            stage1 += u64::from(game.id);
+        }
+        let cubes = game.get_min_cubes();
+        *powers
+            .entry(cubes.colours().map(String::from).collect())
+            .or_default() += cubes.power(cubes.colours());
+    }
+    let colours: BTreeSet<String> = powers.keys().flatten().cloned().collect();
+    let stage2 = powers.get(&colours).copied().unwrap_or_default();
+    Ok((stage1, stage2))
 }
 
 pub struct Day2Solver;
-impl AdventOfCodeDay<'_> for Day2Solver {
-    type ParsedInput = Vec<Game>;
+impl<'a> AdventOfCodeDay<'a> for Day2Solver {
+    type ParsedInput = Games<'a>;
 
     type Part1Output = u64;
 
     type Part2Output = u64;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
-        solve_stage1(input).into()
+        solve_stage1(input, &Cubes::standard_bag())
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        solve_stage2(input).into()
+        solve_stage2(input)
     }
 
-    fn parse_input(input: &'_ str) -> Self::ParsedInput {
+    fn parse_input(input: &'a str) -> Self::ParsedInput {
         parse_games(input).unwrap()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::Cubes;
 
     const TEST_INPUT: &str = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
 Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
@@ -147,11 +201,58 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
     #[test]
     fn test_stage1() {
         let games = super::parse_games(TEST_INPUT).unwrap();
-        assert_eq!(super::solve_stage1(&games), 8);
+        assert_eq!(super::solve_stage1(&games, &Cubes::standard_bag()), 8);
     }
     #[test]
     fn test_stage2() {
         let games = super::parse_games(TEST_INPUT).unwrap();
         assert_eq!(super::solve_stage2(&games), 2286);
     }
+    #[test]
+    fn test_streaming() {
+        assert_eq!(
+            super::solve_streaming(TEST_INPUT.as_bytes(), &Cubes::standard_bag()).unwrap(),
+            (8, 2286)
+        );
+    }
+    #[test]
+    fn test_colours() {
+        const TEST_INPUT: &str = "Game 1: 2 cyan, 1 magenta; 3 yellow
+Game 2: 1 cyan, 4 magenta, 2 yellow, 1 black
+Game 3: 5 cyan; 1 magenta, 1 black, 2 yellow";
+        let games = super::parse_games(TEST_INPUT).unwrap();
+        let colours: Vec<_> = games.colours().collect();
+        assert_eq!(colours, ["black", "cyan", "magenta", "yellow"]);
+        assert!(super::parse_games("Game 1: 2 cyan,").is_err());
+        let bag = Cubes::from_iter([("cyan", 2), ("magenta", 4), ("yellow", 3)]);
+        assert_eq!(super::solve_stage1(&games, &bag), 1);
+        // the first game has no black cubes, so its power is 0
+        assert_eq!(super::solve_stage2(&games), 18);
+        assert_eq!(
+            super::solve_streaming(TEST_INPUT.as_bytes(), &bag).unwrap(),
+            (1, 18)
+        );
+        assert_eq!(
+            super::solve_streaming(
+                "Game 1: 1 red, 1 green, 1 blue, 2 black".as_bytes(),
+                &Cubes::standard_bag()
+            )
+            .unwrap()
+            .1,
+            2
+        );
+    }
+    #[test]
+    fn test_most_likely_bag() {
+        let games = super::parse_games("Game 1: 2 red; 1 blue").unwrap();
+        let bag = Cubes::from_iter([("red", 2), ("blue", 1)]);
+        let p = super::log_likelihood(games.games(), &bag).exp();
+        assert!((p - 1.0 / 9.0).abs() < 1e-9);
+
+        // 3 red and 1 blue beat both 2/1 and 2/2
+        let (bag, ll) = super::most_likely_bag(games.games(), 4).unwrap();
+        assert_eq!(bag, Cubes::from_iter([("red", 3), ("blue", 1)]));
+        assert!((ll.exp() - 1.0 / 8.0).abs() < 1e-9);
+        assert!(super::most_likely_bag(games.games(), 2).is_none());
+    }
 }
@@ -0,0 +1,94 @@
+use crate::{Grid, PartNumber, Symbol};
+
+/// Which symbols a [`GearRule`] applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolClass {
+    Any,
+    OneOf(Vec<char>),
+}
+impl SymbolClass {
+    pub fn contains(&self, symbol: char) -> bool {
+        match self {
+            SymbolClass::Any => true,
+            SymbolClass::OneOf(symbols) => symbols.contains(&symbol),
+        }
+    }
+}
+
+/// How many numbers have to surround a symbol for it to be a gear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NeighbourCount {
+    Exactly(usize),
+    AtLeast(usize),
+}
+impl NeighbourCount {
+    pub fn matches(self, count: usize) -> bool {
+        match self {
+            NeighbourCount::Exactly(n) => count == n,
+            NeighbourCount::AtLeast(n) => count >= n,
+        }
+    }
+}
+
+/// How the numbers around a gear are combined into its ratio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    Product,
+    Sum,
+    Max,
+}
+impl Aggregation {
+    pub fn apply(self, values: impl IntoIterator<Item = u32>) -> u64 {
+        let values = values.into_iter().map(u64::from);
+        match self {
+            Aggregation::Product => values.product(),
+            Aggregation::Sum => values.sum(),
+            Aggregation::Max => values.max().unwrap_or_default(),
+        }
+    }
+}
+
+/// Defines what counts as a gear. The default is the rule of stage 2: a `*` next to exactly two
+/// numbers, whose ratio is their product.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GearRule {
+    pub symbols: SymbolClass,
+    pub neighbours: NeighbourCount,
+    pub aggregation: Aggregation,
+    /// Maximum distance in rows and columns between the symbol and a digit of a number.
+    pub radius: usize,
+}
+impl Default for GearRule {
+    fn default() -> Self {
+        GearRule {
+            symbols: SymbolClass::OneOf(vec!['*']),
+            neighbours: NeighbourCount::Exactly(2),
+            aggregation: Aggregation::Product,
+            radius: 1,
+        }
+    }
+}
+
+/// A symbol matching a [`GearRule`], along with the numbers around it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gear<'a> {
+    pub symbol: Symbol,
+    pub numbers: Vec<&'a PartNumber>,
+    pub ratio: u64,
+}
+
+impl Grid {
+    pub fn find_gears<'a>(&'a self, rule: &'a GearRule) -> impl Iterator<Item = Gear<'a>> + 'a {
+        self.symbols()
+            .iter()
+            .filter(|s| rule.symbols.contains(s.symbol))
+            .filter_map(|&symbol| {
+                let numbers = self.numbers_within(symbol.row, symbol.col, rule.radius);
+                rule.neighbours.matches(numbers.len()).then(|| Gear {
+                    symbol,
+                    ratio: rule.aggregation.apply(numbers.iter().map(|n| n.value)),
+                    numbers,
+                })
+            })
+    }
+}
@@ -1,11 +1,30 @@
-use std::{rc::Rc, str::FromStr};
+use std::{ops::Range, str::FromStr};
 
 use aoc_traits::AdventOfCodeDay;
 use color_eyre::eyre::{Report, Result};
 
-#[derive(Debug)]
+mod gear;
+pub use gear::{Aggregation, Gear, GearRule, NeighbourCount, SymbolClass};
+
+/// A number in the schematic, spanning the columns `col_span` of `row`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartNumber {
+    pub value: u32,
+    pub row: usize,
+    pub col_span: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+    pub symbol: char,
+    pub row: usize,
+    pub col: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
 enum Cell {
-    Number(Rc<u32>),
+    /// Index into [`Grid::numbers`].
+    Number(usize),
     Symbol(char),
     Empty,
 }
@@ -13,75 +32,62 @@ enum Cell {
 #[derive(Debug)]
 pub struct Grid {
     cells: Vec<Vec<Cell>>,
+    numbers: Vec<PartNumber>,
+    symbols: Vec<Symbol>,
 }
 impl Grid {
-    fn get_cell(&self, row: usize, col: usize) -> Option<&Cell> {
-        self.cells.get(row).and_then(|r| r.get(col))
-    }
-    fn has_symbol_neighbor(&self, row: usize, col: usize) -> bool {
-        let mut has_symbol_neighbor = false;
-        for row_idx in (row.saturating_sub(1))..=(row + 1) {
-            for col_idx in (col.saturating_sub(1))..=(col + 1) {
-                if row_idx == row && col_idx == col {
-                    continue;
-                }
-                if let Some(Cell::Symbol(_)) = self.get_cell(row_idx, col_idx) {
-                    has_symbol_neighbor = true;
-                }
-            }
-        }
-        has_symbol_neighbor
-    }
-    fn find_part_nums(&self) -> Vec<u32> {
-        let mut part_nums: Vec<u32> = Vec::new();
-
-        for (row_idx, row) in self.cells.iter().enumerate() {
-            let mut already_done_num = false;
-            for (col_idx, col) in row.iter().enumerate() {
-                match col {
-                    Cell::Number(n) => {
-                        if !already_done_num && self.has_symbol_neighbor(row_idx, col_idx) {
-                            part_nums.push(**n);
-                            already_done_num = true;
-                        }
-                    }
-                    _ => already_done_num = false,
-                }
-            }
-        }
-
-        part_nums
+    fn get_cell(&self, row: usize, col: usize) -> Option<Cell> {
+        self.cells.get(row).and_then(|r| r.get(col)).copied()
     }
-    fn get_number_neighbors(&self, row: usize, col: usize) -> Vec<Rc<u32>> {
-        let mut neighbors: Vec<Rc<u32>> = Vec::new();
-        for row_idx in (row.saturating_sub(1))..=(row + 1) {
-            for col_idx in (col.saturating_sub(1))..=(col + 1) {
-                if row_idx == row && col_idx == col {
-                    continue;
-                }
-                if let Some(Cell::Number(n)) = self.get_cell(row_idx, col_idx) {
-                    neighbors.push(n.clone());
-                }
-            }
-        }
-        neighbors.dedup_by(|a, b| Rc::ptr_eq(a, b));
-        neighbors
-    }
-    fn find_gears(&self) -> Vec<u32> {
-        let mut gears: Vec<u32> = Vec::new();
-
-        for (row_idx, row) in self.cells.iter().enumerate() {
-            for (col_idx, col) in row.iter().enumerate() {
-                if let Cell::Symbol('*') = col {
-                    let neighbors = self.get_number_neighbors(row_idx, col_idx);
-                    if neighbors.len() == 2 {
-                        gears.push(*neighbors[0] * *neighbors[1]);
-                    }
-                }
-            }
-        }
-
-        gears
+    /// All cells in the rectangle around `rows` x `cols`, extended by `radius` in every
+    /// direction.
+    fn surrounding(
+        &self,
+        rows: Range<usize>,
+        cols: Range<usize>,
+        radius: usize,
+    ) -> impl Iterator<Item = (usize, usize, Cell)> + '_ {
+        (rows.start.saturating_sub(radius)..rows.end + radius).flat_map(move |row| {
+            (cols.start.saturating_sub(radius)..cols.end + radius)
+                .filter_map(move |col| self.get_cell(row, col).map(|cell| (row, col, cell)))
+        })
+    }
+    pub fn numbers(&self) -> &[PartNumber] {
+        &self.numbers
+    }
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+    /// The numbers adjacent to the cell at `row`/`col`, each reported once, in reading order.
+    pub fn numbers_touching(&self, row: usize, col: usize) -> Vec<&PartNumber> {
+        self.numbers_within(row, col, 1)
+    }
+    /// Like [`Grid::numbers_touching`], but for numbers up to `radius` rows and columns away.
+    pub fn numbers_within(&self, row: usize, col: usize, radius: usize) -> Vec<&PartNumber> {
+        let mut indices: Vec<usize> = self
+            .surrounding(row..row + 1, col..col + 1, radius)
+            .filter_map(|(_, _, cell)| match cell {
+                Cell::Number(n) => Some(n),
+                _ => None,
+            })
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices.into_iter().map(|n| &self.numbers[n]).collect()
+    }
+    /// The symbols adjacent to any digit of `number`, in reading order.
+    pub fn symbols_touching(&self, number: &PartNumber) -> Vec<Symbol> {
+        self.surrounding(number.row..number.row + 1, number.col_span.clone(), 1)
+            .filter_map(|(row, col, cell)| match cell {
+                Cell::Symbol(symbol) => Some(Symbol { symbol, row, col }),
+                _ => None,
+            })
+            .collect()
+    }
+    pub fn find_part_nums(&self) -> impl Iterator<Item = &PartNumber> {
+        self.numbers
+            .iter()
+            .filter(|n| !self.symbols_touching(n).is_empty())
     }
 }
 
@@ -89,60 +95,67 @@ impl FromStr for Grid {
     type Err = Report;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut cells: Vec<Vec<Cell>> = s
+        let mut numbers: Vec<PartNumber> = Vec::new();
+        let mut symbols = Vec::new();
+        let cells = s
             .lines()
-            .map(|l| {
+            .enumerate()
+            .map(|(row, l)| {
+                let mut current: Option<usize> = None;
                 l.chars()
-                    .map(|c| match c {
-                        '.' => Cell::Empty,
-                        '0'..='9' => Cell::Number(Rc::new(c.to_digit(10).unwrap())),
-                        _ => Cell::Symbol(c),
+                    .enumerate()
+                    .map(|(col, c)| match c {
+                        '0'..='9' => {
+                            let digit = c.to_digit(10).unwrap();
+                            // digits directly following another one extend that number
+                            let n = *current.get_or_insert_with(|| {
+                                numbers.push(PartNumber {
+                                    value: 0,
+                                    row,
+                                    col_span: col..col,
+                                });
+                                numbers.len() - 1
+                            });
+                            let number = &mut numbers[n];
+                            number.value = number.value * 10 + digit;
+                            number.col_span.end = col + 1;
+                            Cell::Number(n)
+                        }
+                        '.' => {
+                            current = None;
+                            Cell::Empty
+                        }
+                        _ => {
+                            current = None;
+                            symbols.push(Symbol {
+                                symbol: c,
+                                row,
+                                col,
+                            });
+                            Cell::Symbol(c)
+                        }
                     })
                     .collect()
             })
             .collect();
-
-        // then, fuse numbers
-        for row in cells.iter_mut() {
-            let mut current_nums: Vec<&mut Rc<u32>> = Vec::new();
-            for cell in row.iter_mut() {
-                match cell {
-                    Cell::Number(n) => current_nums.push(n),
-                    _ => {
-                        if current_nums.len() > 1 {
-                            let sum =
-                                Rc::new(current_nums.iter().fold(0u32, |acc, x| 10 * acc + ***x));
-
-                            for n in current_nums.iter_mut() {
-                                **n = sum.clone();
-                            }
-                        }
-                        current_nums.clear();
-                    }
-                }
-            }
-            if current_nums.len() > 1 {
-                let sum = Rc::new(current_nums.iter().fold(0u32, |acc, x| 10 * acc + ***x));
-
-                for n in current_nums.iter_mut() {
-                    **n = sum.clone();
-                }
-            }
-        }
-        Ok(Grid { cells })
+        Ok(Grid {
+            cells,
+            numbers,
+            symbols,
+        })
     }
 }
 
 #[cfg(test)]
 fn solve_stage1(input: &str) -> Result<u32> {
     let grid = Grid::from_str(input)?;
-    Ok(grid.find_part_nums().iter().sum())
+    Ok(grid.find_part_nums().map(|n| n.value).sum())
 }
 
 #[cfg(test)]
-fn solve_stage2(input: &str) -> Result<u32> {
+fn solve_stage2(input: &str) -> Result<u64> {
     let grid = Grid::from_str(input)?;
-    Ok(grid.find_gears().iter().sum())
+    Ok(grid.find_gears(&GearRule::default()).map(|g| g.ratio).sum())
 }
 
 pub struct Day3Solver;
@@ -152,23 +165,29 @@ impl AdventOfCodeDay<'_> for Day3Solver {
 
     type Part1Output = u32;
 
-    type Part2Output = u32;
+    type Part2Output = u64;
 
     fn parse_input(input: &str) -> Self::ParsedInput {
         Grid::from_str(input).unwrap()
     }
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
-        input.find_part_nums().iter().sum()
+        input.find_part_nums().map(|n| n.value).sum()
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        input.find_gears().iter().sum()
+        input
+            .find_gears(&GearRule::default())
+            .map(|g| g.ratio)
+            .sum()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
+    use crate::{Aggregation, GearRule, Grid, NeighbourCount, Symbol, SymbolClass};
 
     const TEST_INPUT: &str = "467..114..
 ...*......
@@ -188,4 +207,56 @@ mod tests {
     fn test_stage2() {
         assert_eq!(super::solve_stage2(TEST_INPUT).unwrap(), 467835);
     }
+    #[test]
+    fn test_index() {
+        let grid = Grid::from_str("1.2\n.*.\n333\n..#").unwrap();
+        let touching: Vec<_> = grid
+            .numbers_touching(1, 1)
+            .iter()
+            .map(|n| n.value)
+            .collect();
+        assert_eq!(touching, [1, 2, 333]);
+        let number = grid.numbers().iter().find(|n| n.value == 333).unwrap();
+        assert_eq!((number.row, number.col_span.clone()), (2, 0..3));
+        assert_eq!(
+            grid.symbols_touching(number),
+            [
+                Symbol {
+                    symbol: '*',
+                    row: 1,
+                    col: 1
+                },
+                Symbol {
+                    symbol: '#',
+                    row: 3,
+                    col: 2
+                }
+            ]
+        );
+        // three numbers around the `*`, so it is no gear
+        assert_eq!(grid.find_gears(&GearRule::default()).count(), 0);
+    }
+    #[test]
+    fn test_gear_rules() {
+        let grid = Grid::from_str(TEST_INPUT).unwrap();
+        let rule = GearRule {
+            symbols: SymbolClass::Any,
+            neighbours: NeighbourCount::AtLeast(1),
+            aggregation: Aggregation::Sum,
+            radius: 1,
+        };
+        // every number touches exactly one symbol here, so this sums up all part numbers
+        assert_eq!(grid.find_gears(&rule).map(|g| g.ratio).sum::<u64>(), 4361);
+
+        let rule = GearRule {
+            symbols: SymbolClass::OneOf(vec!['$', '#']),
+            neighbours: NeighbourCount::Exactly(3),
+            aggregation: Aggregation::Max,
+            radius: 2,
+        };
+        let gears: Vec<_> = grid.find_gears(&rule).collect();
+        assert_eq!(gears.len(), 1);
+        assert_eq!(gears[0].symbol.symbol, '$');
+        assert_eq!(gears[0].ratio, 664);
+    }
 }
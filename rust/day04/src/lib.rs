@@ -1,3 +1,9 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    io::BufRead,
+};
+
+use answer::{Answer, Overflow};
 use aoc_traits::AdventOfCodeDay;
 use color_eyre::Result;
 use nom::{
@@ -11,30 +17,82 @@ use nom::{
 
 #[derive(Debug)]
 pub struct Card {
-    _id: u32,
-    winning_numbers: Vec<u32>,
+    id: u32,
+    winning_numbers: HashSet<u32>,
     numbers: Vec<u32>,
 }
 
 impl Card {
-    fn win_value(&self) -> u32 {
-        let num_winners = self
-            .numbers
-            .iter()
-            .filter(|x| self.winning_numbers.contains(x))
-            .count();
-
-        match num_winners {
-            0 => 0,
-            x => 1 << (x - 1),
+    /// The points of the card, or [`Overflow`] if it has more than 64 matches.
+    fn win_value(&self) -> Result<u64, Overflow> {
+        match self.winners() {
+            0 => Ok(0),
+            x => 1u64.checked_shl(x - 1).ok_or(Overflow),
         }
     }
 
-    fn winners(&self) -> u32 {
+    /// The numbers of the card that are winning numbers, in the order they appear on the card.
+    pub fn matched(&self) -> impl Iterator<Item = u32> + '_ {
         self.numbers
             .iter()
+            .copied()
             .filter(|x| self.winning_numbers.contains(x))
-            .count() as u32
+    }
+
+    fn winners(&self) -> u32 {
+        self.matched().count() as u32
+    }
+}
+
+/// How a single card took part in the cascade of stage 2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardCascade {
+    pub id: u32,
+    /// Copies of this card, including the original.
+    pub copies: u64,
+    pub matched: Vec<u32>,
+    /// Copies this card added to each of the following cards, by their id.
+    pub contributions: Vec<(u32, u64)>,
+}
+
+/// The result of stage 2, card by card.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cascade {
+    pub cards: Vec<CardCascade>,
+}
+
+impl Cascade {
+    /// Plays the cascade, or returns [`Overflow`] if the copies of a card do not fit into a
+    /// `u64`. They can roughly double from card to card.
+    pub fn new(cards: &[Card]) -> Result<Cascade, Overflow> {
+        let mut copies = vec![1u64; cards.len()];
+        let cards = cards
+            .iter()
+            .enumerate()
+            .map(|(i, card)| {
+                let matched: Vec<_> = card.matched().collect();
+                let won = (i + 1..cards.len()).take(matched.len());
+                let contributions = won
+                    .map(|j| {
+                        copies[j] = copies[j].checked_add(copies[i]).ok_or(Overflow)?;
+                        Ok((cards[j].id, copies[i]))
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(CardCascade {
+                    id: card.id,
+                    copies: copies[i],
+                    matched,
+                    contributions,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Cascade { cards })
+    }
+
+    pub fn total_cards(&self) -> Result<u64, Overflow> {
+        self.cards
+            .iter()
+            .try_fold(0u64, |acc, c| acc.checked_add(c.copies).ok_or(Overflow))
     }
 }
 
@@ -55,8 +113,8 @@ fn parse_card(input: &str) -> IResult<&str, Card> {
     Ok((
         input,
         Card {
-            _id: id,
-            winning_numbers,
+            id,
+            winning_numbers: winning_numbers.into_iter().collect(),
             numbers,
         },
     ))
@@ -65,40 +123,89 @@ fn parse_card(input: &str) -> IResult<&str, Card> {
 fn parse_cards(input: &str) -> Result<Vec<Card>> {
     let (_, cards) = all_consuming(separated_list1(line_ending, parse_card))(input)
         .map_err(|e| color_eyre::eyre::eyre!("Failed to parse input: {}", e))?;
+    for pair in cards.windows(2) {
+        check_next_id(pair[0].id, &pair[1])?;
+    }
     Ok(cards)
 }
 
-fn solve_stage1(cards: &[Card]) -> u32 {
-    cards.iter().map(|g| g.win_value()).sum::<u32>()
+/// Copies are won for the cards directly following a card, so the ids have to be contiguous.
+fn check_next_id(previous: u32, card: &Card) -> Result<()> {
+    if card.id != previous + 1 {
+        return Err(color_eyre::eyre::eyre!(
+            "Card {} follows card {}, expected card {}",
+            card.id,
+            previous,
+            previous + 1
+        ));
+    }
+    Ok(())
+}
+
+fn solve_stage1(cards: &[Card]) -> Result<u64, Overflow> {
+    cards.iter().try_fold(0u64, |acc, g| {
+        acc.checked_add(g.win_value()?).ok_or(Overflow)
+    })
+}
+fn solve_stage2(cards: &[Card]) -> Result<u64, Overflow> {
+    Cascade::new(cards)?.total_cards()
 }
-fn solve_stage2(cards: &[Card]) -> u32 {
-    let mut winners: Vec<_> = cards.iter().map(|g| (1, g.winners())).collect();
-    for i in 0..winners.len() {
-        let wins = winners[i].1;
-        let count = winners[i].0;
-        for j in 0..wins as usize {
-            if let Some(card) = winners.get_mut(i + j + 1) {
-                card.0 += count;
+
+/// Solves both stages while reading `input` one card per line. Stage 2 only keeps the
+/// copies won for the next few cards, so memory is bounded by the largest number of winners
+/// on a single card instead of the number of cards.
+pub fn solve_streaming<R: BufRead>(input: R) -> Result<(u64, u64)> {
+    let mut pending = VecDeque::<u64>::new();
+    let (mut stage1, mut stage2) = (0, 0);
+    let mut previous = None;
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let (_, card) = all_consuming(parse_card)(&line)
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to parse line {}: {}", i + 1, e))?;
+        if let Some(previous) = previous {
+            check_next_id(previous, &card)?;
+        }
+        previous = Some(card.id);
+        stage1 = card
+            .win_value()
+            .ok()
+            .and_then(|points| points.checked_add(stage1))
+            .ok_or_else(|| color_eyre::eyre::eyre!("The points overflow at card {}", card.id))?;
+
+        let overflow = || color_eyre::eyre::eyre!("The copies overflow at card {}", card.id);
+        let count = pending
+            .pop_front()
+            .unwrap_or(0)
+            .checked_add(1)
+            .ok_or_else(overflow)?;
+        stage2 = count.checked_add(stage2).ok_or_else(overflow)?;
+        for j in 0..card.winners() as usize {
+            match pending.get_mut(j) {
+                Some(copies) => *copies = copies.checked_add(count).ok_or_else(overflow)?,
+                None => pending.push_back(count),
             }
         }
     }
-    winners.iter().map(|c| c.0).sum()
+    Ok((stage1, stage2))
 }
 
 pub struct Day4Solver;
 impl AdventOfCodeDay<'_> for Day4Solver {
     type ParsedInput = Vec<Card>;
 
-    type Part1Output = u64;
+    type Part1Output = Answer;
 
-    type Part2Output = u64;
+    type Part2Output = Answer;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
-        solve_stage1(input).into()
+        Answer::from(solve_stage1(input))
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        solve_stage2(input).into()
+        Answer::from(solve_stage2(input))
     }
 
     fn parse_input(input: &'_ str) -> Self::ParsedInput {
@@ -118,11 +225,67 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
     #[test]
     fn test_stage1() {
         let cards = super::parse_cards(TEST_INPUT).unwrap();
-        assert_eq!(super::solve_stage1(&cards), 13);
+        assert_eq!(super::solve_stage1(&cards), Ok(13));
     }
     #[test]
     fn test_stage2() {
         let cards = super::parse_cards(TEST_INPUT).unwrap();
-        assert_eq!(super::solve_stage2(&cards), 30);
+        assert_eq!(super::solve_stage2(&cards), Ok(30));
+    }
+    #[test]
+    fn test_streaming() {
+        assert_eq!(
+            super::solve_streaming(TEST_INPUT.as_bytes()).unwrap(),
+            (13, 30)
+        );
+    }
+    #[test]
+    fn test_cascade() {
+        let cards = super::parse_cards(TEST_INPUT).unwrap();
+        let cascade = super::Cascade::new(&cards).unwrap();
+        let copies: Vec<_> = cascade.cards.iter().map(|c| c.copies).collect();
+        assert_eq!(copies, [1, 2, 4, 8, 14, 1]);
+        assert_eq!(cascade.cards[0].matched, [83, 86, 17, 48]);
+        assert_eq!(cascade.cards[2].contributions, [(4, 4), (5, 4)]);
+
+        assert!(super::parse_cards("Card 1: 1 | 1\nCard 3: 1 | 2").is_err());
+    }
+    #[test]
+    fn test_large_card() {
+        let card = |id: u32, matches: u32| {
+            let numbers = (1..=matches).map(|x| x.to_string()).collect::<Vec<_>>();
+            let numbers = numbers.join(" ");
+            format!("Card {}: {} | {} 999", id, numbers, numbers)
+        };
+        let input = [card(1, 40), card(2, 1)].join("\n");
+        let cards = super::parse_cards(&input).unwrap();
+        assert_eq!(super::solve_stage1(&cards), Ok((1 << 39) + 1));
+        assert_eq!(
+            super::solve_streaming(input.as_bytes()).unwrap(),
+            ((1 << 39) + 1, 3)
+        );
+
+        let input = [card(1, 64), card(2, 64)].join("\n");
+        let cards = super::parse_cards(&input).unwrap();
+        assert_eq!(super::solve_stage1(&cards), Err(answer::Overflow));
+        assert!(super::solve_streaming(input.as_bytes()).is_err());
+        let cards = super::parse_cards(&card(1, 65)).unwrap();
+        assert_eq!(super::solve_stage1(&cards), Err(answer::Overflow));
+        assert_eq!(super::solve_stage2(&cards), Ok(1));
+
+        // the copies about double from card to card
+        let cascade = |count: u32| (1..=count).map(|id| card(id, 10)).collect::<Vec<_>>();
+        let input = cascade(30).join("\n");
+        let cards = super::parse_cards(&input).unwrap();
+        let stage2 = super::solve_stage2(&cards).unwrap();
+        assert_eq!(
+            super::solve_streaming(input.as_bytes()).unwrap(),
+            (30 * 512, stage2)
+        );
+        let input = cascade(70).join("\n");
+        let cards = super::parse_cards(&input).unwrap();
+        assert_eq!(super::solve_stage1(&cards), Ok(70 * 512));
+        assert_eq!(super::solve_stage2(&cards), Err(answer::Overflow));
+        assert!(super::solve_streaming(input.as_bytes()).is_err());
     }
 }
@@ -0,0 +1,186 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
+    ops::Range,
+};
+
+use color_eyre::{eyre, Result};
+
+use crate::{Game, MappingRange, PiecewiseMap};
+
+/// A block of the almanac, converting category `from` to category `to`.
+#[derive(Debug)]
+pub struct Conversion {
+    pub from: String,
+    pub to: String,
+    pub(crate) ranges: Vec<MappingRange>,
+}
+
+/// A problem with the source ranges of a [`Conversion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeIssue {
+    Overlap {
+        block: String,
+        first: Range<u64>,
+        second: Range<u64>,
+    },
+    /// Values in a gap are mapped to themselves, which the puzzle allows.
+    Gap { block: String, gap: Range<u64> },
+}
+
+impl Display for RangeIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RangeIssue::Overlap {
+                block,
+                first,
+                second,
+            } => write!(f, "{}: ranges {:?} and {:?} overlap", block, first, second),
+            RangeIssue::Gap { block, gap } => write!(f, "{}: {:?} is not covered", block, gap),
+        }
+    }
+}
+
+impl Conversion {
+    pub fn map(&self) -> PiecewiseMap {
+        PiecewiseMap::from_ranges(&self.ranges)
+    }
+
+    /// Overlaps and gaps between the source ranges of the block.
+    pub fn issues(&self) -> Vec<RangeIssue> {
+        let block = format!("{}-to-{}", self.from, self.to);
+        let mut ranges: Vec<_> = self.ranges.iter().map(|r| r.range.clone()).collect();
+        ranges.sort_unstable_by_key(|r| (r.start, r.end));
+        ranges
+            .windows(2)
+            .filter_map(|pair| {
+                let (first, second) = (&pair[0], &pair[1]);
+                if second.start < first.end {
+                    Some(RangeIssue::Overlap {
+                        block: block.clone(),
+                        first: first.clone(),
+                        second: second.clone(),
+                    })
+                } else if second.start > first.end {
+                    Some(RangeIssue::Gap {
+                        block: block.clone(),
+                        gap: first.end..second.start,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl Game {
+    pub fn conversions(&self) -> &[Conversion] {
+        &self.conversions
+    }
+
+    /// Reports every overlap and gap in any block of the almanac.
+    pub fn validate(&self) -> Result<(), Vec<RangeIssue>> {
+        let issues: Vec<_> = self.conversions.iter().flat_map(|c| c.issues()).collect();
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// The blocks along the shortest chain from category `from` to category `to`, each with
+    /// whether it is used backwards.
+    fn steps(&self, from: &str, to: &str) -> Result<Vec<(&Conversion, bool)>> {
+        // for every reached category: the category before it, the block and its direction
+        let mut previous: HashMap<&str, (&str, &Conversion, bool)> = HashMap::new();
+        let mut seen = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                break;
+            }
+            for conversion in &self.conversions {
+                let (next, inverted) = if conversion.from == category {
+                    (conversion.to.as_str(), false)
+                } else if conversion.to == category {
+                    (conversion.from.as_str(), true)
+                } else {
+                    continue;
+                };
+                if seen.insert(next) {
+                    previous.insert(next, (category, conversion, inverted));
+                    queue.push_back(next);
+                }
+            }
+        }
+        if !seen.contains(to) {
+            return Err(eyre::eyre!("No conversion from {} to {}", from, to));
+        }
+
+        let mut steps = Vec::new();
+        let mut category = to;
+        while let Some(&(before, conversion, inverted)) = previous.get(category) {
+            steps.push((conversion, inverted));
+            category = before;
+        }
+        steps.reverse();
+        Ok(steps)
+    }
+
+    /// Composes the blocks along the shortest chain from category `from` to category `to`.
+    /// Blocks are used backwards where needed, which requires them to be invertible, see
+    /// [`Game::convert_range`] for blocks that are not.
+    pub fn conversion(&self, from: &str, to: &str) -> Result<PiecewiseMap> {
+        self.steps(from, to)?.into_iter().try_fold(
+            PiecewiseMap::identity(),
+            |map, (conversion, inverted)| {
+                let step = match inverted {
+                    false => conversion.map(),
+                    true => conversion.map().inverse().map_err(|e| {
+                        eyre::eyre!(
+                            "Cannot invert {}-to-{}: {}",
+                            conversion.from,
+                            conversion.to,
+                            e
+                        )
+                    })?,
+                };
+                Ok(map.then(&step))
+            },
+        )
+    }
+
+    /// All values of category `to` that are connected to a value of category `from` in `range`,
+    /// as sorted and disjoint ranges. Blocks used backwards contribute every value mapped into
+    /// the current ranges, so unlike [`Game::conversion`] this works for any almanac.
+    pub fn convert_range(
+        &self,
+        from: &str,
+        to: &str,
+        range: Range<u64>,
+    ) -> Result<Vec<Range<u64>>> {
+        let steps = self.steps(from, to)?;
+        Ok(steps
+            .into_iter()
+            .fold(vec![range], |ranges, (conversion, inverted)| {
+                let map = conversion.map();
+                let mut next: Vec<_> = ranges
+                    .into_iter()
+                    .flat_map(|r| match inverted {
+                        false => map.apply_to_range(r),
+                        true => map.preimage(r),
+                    })
+                    .collect();
+                next.sort_unstable_by_key(|r| r.start);
+                let mut merged: Vec<Range<u64>> = Vec::with_capacity(next.len());
+                for r in next.into_iter().filter(|r| !r.is_empty()) {
+                    match merged.last_mut() {
+                        Some(last) if last.end >= r.start => last.end = last.end.max(r.end),
+                        _ => merged.push(r),
+                    }
+                }
+                merged
+            }))
+    }
+}
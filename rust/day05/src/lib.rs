@@ -14,56 +14,17 @@ use nom::{
     IResult,
 };
 
+mod category;
+mod piecewise;
+pub use category::{Conversion, RangeIssue};
+pub use piecewise::PiecewiseMap;
+
 #[derive(Debug)]
 pub struct MappingRange {
     range: Range<u64>,
     map_start: u64,
 }
 
-impl MappingRange {
-    fn map(&self, input: u64) -> Option<u64> {
-        if self.range.contains(&input) {
-            Some(input - self.range.start + self.map_start)
-        } else {
-            None
-        }
-    }
-}
-
-fn apply_ranges_to_range(mapping_ranges: &[MappingRange], range: Range<u64>) -> Vec<Range<u64>> {
-    let mut result = Vec::new();
-    let mut ranges = vec![range];
-    for map_range in mapping_ranges {
-        // find the overlap of the two ranges
-        ranges = ranges
-            .into_iter()
-            .flat_map(|r| {
-                let maybe_map = map_range.range.start.max(r.start)..map_range.range.end.min(r.end);
-                if maybe_map.is_empty() {
-                    return vec![r];
-                }
-
-                //otherwise, map the overlap
-                result.push(
-                    maybe_map.start - map_range.range.start + map_range.map_start
-                        ..maybe_map.end - map_range.range.start + map_range.map_start,
-                );
-                vec![
-                    r.start..maybe_map.start, // do not map this
-                    maybe_map.end..r.end,     // do not map this
-                ]
-            })
-            .filter(|r| !r.is_empty()) // throw out empty ranges
-            .collect();
-    }
-    // all unmapped ranges go into result
-    result.extend(ranges);
-    result
-}
-fn apply_ranges(ranges: &[MappingRange], input: u64) -> u64 {
-    ranges.iter().find_map(|r| r.map(input)).unwrap_or(input)
-}
-
 fn parse_u64(input: &str) -> IResult<&str, u64> {
     map_res(digit1, str::parse::<u64>)(input)
 }
@@ -80,18 +41,26 @@ fn parse_mapping(input: &str) -> IResult<&str, MappingRange> {
     ))
 }
 
-fn parse_mapping_block(input: &str) -> IResult<&str, Vec<MappingRange>> {
-    let (input, (_from, _, _to, _)) = terminated(
+fn parse_mapping_block(input: &str) -> IResult<&str, Conversion> {
+    let (input, (from, _, to, _)) = terminated(
         tuple((alpha1, tag("-to-"), alpha1, tag(" map:"))),
         line_ending,
     )(input)?;
-    separated_list1(line_ending, parse_mapping)(input)
+    let (input, ranges) = separated_list1(line_ending, parse_mapping)(input)?;
+    Ok((
+        input,
+        Conversion {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            ranges,
+        },
+    ))
 }
 
 #[derive(Debug)]
 pub struct Game {
     seeds: Vec<u64>,
-    mappings: Vec<Vec<MappingRange>>,
+    conversions: Vec<Conversion>,
 }
 
 fn parse_game(input: &str) -> IResult<&str, Game> {
@@ -103,10 +72,17 @@ fn parse_game(input: &str) -> IResult<&str, Game> {
     // skip a line
     let (input, _) = line_ending(input)?;
 
-    let (input, mappings) =
+    let (input, conversions) =
         all_consuming(separated_list1(count(line_ending, 2), parse_mapping_block))(input)?;
 
-    Ok((input, Game { seeds, mappings }))
+    Ok((input, Game { seeds, conversions }))
+}
+
+impl Game {
+    /// All blocks of the almanac composed into a single map from seed to location.
+    pub fn seed_to_location(&self) -> Result<PiecewiseMap> {
+        self.conversion("seed", "location")
+    }
 }
 
 fn parse(input: &str) -> Result<Game> {
@@ -115,44 +91,24 @@ fn parse(input: &str) -> Result<Game> {
         .map(|x| x.1)
 }
 
-fn apply_mappings(mappings: &[Vec<MappingRange>], input: u64) -> u64 {
-    let mut s = input;
-    for mapping in mappings {
-        s = apply_ranges(mapping, s);
-    }
-    s
-}
-fn apply_mappings_to_ranges(
-    mappings: &[Vec<MappingRange>],
-    ranges: Vec<Range<u64>>,
-) -> Vec<Range<u64>> {
-    let mut s = ranges;
-    for mapping in mappings {
-        s = s
-            .into_iter()
-            .flat_map(|r| apply_ranges_to_range(mapping, r))
-            .collect();
-    }
-    s
-}
-
-fn solve_stage1(input: &Game) -> u64 {
-    input
+fn solve_stage1(input: &Game) -> Result<u64> {
+    let map = input.seed_to_location()?;
+    Ok(input
         .seeds
         .iter()
-        .map(|&s| apply_mappings(&input.mappings, s))
+        .map(|&s| map.apply(s))
         .min()
-        .unwrap_or_default()
+        .unwrap_or_default())
 }
 
-fn solve_stage2(input: &Game) -> u64 {
-    let ranges = input.seeds.chunks(2).map(|s| s[0]..(s[0] + s[1])).collect();
-    let mapped_ranges = apply_mappings_to_ranges(&input.mappings, ranges);
-    mapped_ranges
-        .iter()
-        .map(|r| r.start)
+fn solve_stage2(input: &Game) -> Result<u64> {
+    let map = input.seed_to_location()?;
+    Ok(input
+        .seeds
+        .chunks(2)
+        .filter_map(|s| map.min_over(s[0]..(s[0] + s[1])))
         .min()
-        .unwrap_or_default()
+        .unwrap_or_default())
 }
 
 pub struct Day5Solver;
@@ -164,11 +120,11 @@ impl AdventOfCodeDay<'_> for Day5Solver {
     type Part2Output = u64;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
-        solve_stage1(input)
+        solve_stage1(input).unwrap()
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        solve_stage2(input)
+        solve_stage2(input).unwrap()
     }
 
     fn parse_input(input: &'_ str) -> Self::ParsedInput {
@@ -215,11 +171,71 @@ humidity-to-location map:
     #[test]
     fn test_stage1() {
         let input = super::parse(TEST_INPUT).unwrap();
-        assert_eq!(super::solve_stage1(&input), 35);
+        assert_eq!(super::solve_stage1(&input).unwrap(), 35);
     }
     #[test]
     fn test_stage2() {
         let input = super::parse(TEST_INPUT).unwrap();
-        assert_eq!(super::solve_stage2(&input), 46);
+        assert_eq!(super::solve_stage2(&input).unwrap(), 46);
+    }
+    #[test]
+    fn test_composition() {
+        let input = super::parse(TEST_INPUT).unwrap();
+        let map = input.seed_to_location().unwrap();
+        let locations: Vec<_> = [79, 14, 55, 13].map(|s| map.apply(s)).into();
+        assert_eq!(locations, [82, 43, 86, 35]);
+
+        // seed 82 is the only one ending up in location 46
+        let inverse = map.inverse().unwrap();
+        let seeds = inverse.apply_to_range(46..47);
+        assert_eq!((seeds.len(), seeds[0].start), (1, 82));
+        assert_eq!(inverse.then(&map), super::PiecewiseMap::identity());
+        assert_eq!(map.preimage(46..47), seeds);
+        let location_to_seed = input.convert_range("location", "seed", 46..47).unwrap();
+        assert_eq!(location_to_seed, seeds);
+
+        // 0 is reached from three seeds, so only the preimage exists
+        let input = super::parse("seeds: 1\n\nseed-to-soil map:\n0 10 5\n0 20 5").unwrap();
+        let map = input.conversion("seed", "soil").unwrap();
+        assert!(map.inverse().is_err());
+        assert_eq!(map.preimage(0..2), [0..2, 10..12, 20..22]);
+        assert!(input.conversion("soil", "seed").is_err());
+        assert_eq!(
+            input.convert_range("soil", "seed", 3..7).unwrap(),
+            [3..7, 13..15, 23..25]
+        );
+    }
+    #[test]
+    fn test_categories() {
+        let input = super::parse(TEST_INPUT).unwrap();
+        // humidity 78 is reached from soil 81 (seed 79)
+        let humidity_to_soil = input.conversion("humidity", "soil").unwrap();
+        assert_eq!(humidity_to_soil.apply(78), 81);
+        assert_eq!(input.conversion("soil", "soil").unwrap().apply(5), 5);
+        assert!(input.conversion("seed", "nothing").is_err());
+
+        assert_eq!(input.validate(), Ok(()));
+        let gapped = TEST_INPUT.replace("57 7 4", "57 8 3");
+        let issues = super::parse(&gapped).unwrap().validate().unwrap_err();
+        assert_eq!(
+            issues,
+            [super::RangeIssue::Gap {
+                block: "fertilizer-to-water".to_owned(),
+                gap: 7..8
+            }]
+        );
+
+        // overlapping ranges are solved like the puzzle does, the first range wins
+        let overlapping = super::parse(&TEST_INPUT.replace("52 50 48", "52 50 49")).unwrap();
+        assert_eq!(
+            overlapping.validate().unwrap_err(),
+            [super::RangeIssue::Overlap {
+                block: "seed-to-soil".to_owned(),
+                first: 50..99,
+                second: 98..100
+            }]
+        );
+        let seed_to_soil = overlapping.conversion("seed", "soil").unwrap();
+        assert_eq!((seed_to_soil.apply(98), seed_to_soil.apply(97)), (50, 99));
     }
 }
@@ -0,0 +1,193 @@
+use std::ops::Range;
+
+use color_eyre::{eyre, Result};
+
+use crate::MappingRange;
+
+/// One past the largest value of the domain.
+const END: i128 = 1 << 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Piece {
+    start: u64,
+    offset: i128,
+}
+
+/// A map from `u64` to `u64` that shifts consecutive intervals of its domain by a constant
+/// offset each.
+///
+/// The pieces are sorted and cover the whole domain, so looking up a value is a binary search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiecewiseMap {
+    pieces: Vec<Piece>,
+}
+
+impl PiecewiseMap {
+    pub fn identity() -> PiecewiseMap {
+        PiecewiseMap {
+            pieces: vec![Piece {
+                start: 0,
+                offset: 0,
+            }],
+        }
+    }
+
+    /// The map of a single almanac block. Like in the almanac, the first matching range wins
+    /// and values outside of all ranges are mapped to themselves.
+    pub fn from_ranges(ranges: &[MappingRange]) -> PiecewiseMap {
+        let mut map = PiecewiseMap::identity();
+        for range in ranges.iter().rev() {
+            let offset = i128::from(range.map_start) - i128::from(range.range.start);
+            map.overwrite(range.range.clone(), offset);
+        }
+        map
+    }
+
+    /// Merges neighbouring pieces with the same offset.
+    fn from_pieces(mut pieces: Vec<Piece>) -> PiecewiseMap {
+        pieces.dedup_by(|next, piece| next.offset == piece.offset);
+        PiecewiseMap { pieces }
+    }
+
+    fn end(&self, i: usize) -> i128 {
+        self.pieces.get(i + 1).map_or(END, |p| i128::from(p.start))
+    }
+
+    fn overwrite(&mut self, range: Range<u64>, offset: i128) {
+        let (start, end) = (i128::from(range.start), i128::from(range.end));
+        if start >= end {
+            return;
+        }
+        let mut pieces = Vec::with_capacity(self.pieces.len() + 2);
+        for (i, &piece) in self.pieces.iter().enumerate() {
+            let (piece_start, piece_end) = (i128::from(piece.start), self.end(i));
+            if piece_end <= start || piece_start >= end {
+                pieces.push(piece);
+                continue;
+            }
+            if piece_start < start {
+                pieces.push(piece);
+            }
+            if piece_start <= start {
+                pieces.push(Piece {
+                    start: range.start,
+                    offset,
+                });
+            }
+            if piece_end > end {
+                pieces.push(Piece {
+                    start: range.end,
+                    offset: piece.offset,
+                });
+            }
+        }
+        *self = PiecewiseMap::from_pieces(pieces);
+    }
+
+    /// The parts of `start..end` that fall into each piece, along with the offset of the piece.
+    fn segments(&self, start: i128, end: i128) -> impl Iterator<Item = (i128, i128, i128)> + '_ {
+        let first = self
+            .pieces
+            .partition_point(|p| i128::from(p.start) <= start)
+            .saturating_sub(1);
+        (first..self.pieces.len())
+            .take_while(move |&i| i128::from(self.pieces[i].start) < end)
+            .map(move |i| {
+                let piece_start = i128::from(self.pieces[i].start);
+                (
+                    start.max(piece_start),
+                    end.min(self.end(i)),
+                    self.pieces[i].offset,
+                )
+            })
+            .filter(|(start, end, _)| start < end)
+    }
+
+    pub fn apply(&self, input: u64) -> u64 {
+        let i = self.pieces.partition_point(|p| p.start <= input) - 1;
+        (i128::from(input) + self.pieces[i].offset) as u64
+    }
+
+    /// The image of `range`, as one range per piece it overlaps.
+    pub fn apply_to_range(&self, range: Range<u64>) -> Vec<Range<u64>> {
+        self.segments(i128::from(range.start), i128::from(range.end))
+            .map(|(start, end, offset)| (start + offset) as u64..(end + offset) as u64)
+            .collect()
+    }
+
+    /// The smallest value `range` is mapped to, `None` if the range is empty.
+    pub fn min_over(&self, range: Range<u64>) -> Option<u64> {
+        // every piece is increasing, so its minimum is at the start of the overlap
+        self.segments(i128::from(range.start), i128::from(range.end))
+            .map(|(start, _, offset)| (start + offset) as u64)
+            .min()
+    }
+
+    /// All values that are mapped into `range`, as sorted and disjoint ranges. Unlike
+    /// [`PiecewiseMap::inverse`] this also works for maps that reach a value more than once.
+    pub fn preimage(&self, range: Range<u64>) -> Vec<Range<u64>> {
+        let (start, end) = (i128::from(range.start), i128::from(range.end));
+        let mut result: Vec<Range<u64>> = Vec::new();
+        for (i, piece) in self.pieces.iter().enumerate() {
+            let image_start = start.max(i128::from(piece.start) + piece.offset);
+            let image_end = end.min(self.end(i) + piece.offset);
+            if image_start >= image_end {
+                continue;
+            }
+            let found = (image_start - piece.offset) as u64..(image_end - piece.offset) as u64;
+            match result.last_mut() {
+                Some(last) if last.end == found.start => last.end = found.end,
+                _ => result.push(found),
+            }
+        }
+        result
+    }
+
+    /// Applies `self` first, then `next`.
+    pub fn then(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let mut pieces = Vec::new();
+        for (i, piece) in self.pieces.iter().enumerate() {
+            let (start, end) = (i128::from(piece.start), self.end(i));
+            for (image_start, _, offset) in next.segments(start + piece.offset, end + piece.offset)
+            {
+                pieces.push(Piece {
+                    start: (image_start - piece.offset) as u64,
+                    offset: piece.offset + offset,
+                });
+            }
+        }
+        PiecewiseMap::from_pieces(pieces)
+    }
+
+    /// The map going the other way, which only exists if every value is reached exactly once.
+    pub fn inverse(&self) -> Result<PiecewiseMap> {
+        let mut images: Vec<_> = (0..self.pieces.len())
+            .map(|i| {
+                let piece = self.pieces[i];
+                let start = i128::from(piece.start) + piece.offset;
+                (start, self.end(i) + piece.offset, piece.offset)
+            })
+            .collect();
+        images.sort_unstable();
+        let mut expected = 0;
+        for &(start, end, _) in &images {
+            if start != expected {
+                return Err(eyre::eyre!(
+                    "Map is not invertible, {} is reached {}",
+                    start.min(expected),
+                    if start < expected { "twice" } else { "never" }
+                ));
+            }
+            expected = end;
+        }
+        Ok(PiecewiseMap::from_pieces(
+            images
+                .into_iter()
+                .map(|(start, _, offset)| Piece {
+                    start: start as u64,
+                    offset: -offset,
+                })
+                .collect(),
+        ))
+    }
+}
@@ -1,3 +1,6 @@
+use std::io::BufRead;
+
+use answer::{Answer, Overflow};
 use aoc_traits::AdventOfCodeDay;
 use color_eyre::{
     eyre::{self},
@@ -18,11 +21,58 @@ pub struct Game {
     distance: u64,
 }
 impl Game {
+    pub fn new(time: u64, distance: u64) -> Game {
+        Game { time, distance }
+    }
     fn ways_to_beat(&self) -> u64 {
-        (1..self.time)
-            .map(|t| (self.time - t) * t)
-            .filter(|x| *x > self.distance)
-            .count() as u64
+        // acceleration * time always fits into u64 for the standard boat
+        Boat::default().ways_to_beat(self).unwrap()
+    }
+}
+
+/// A boat whose speed grows by `acceleration` mm/ms for every ms the button is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Boat {
+    pub acceleration: u64,
+}
+impl Default for Boat {
+    fn default() -> Self {
+        Boat { acceleration: 1 }
+    }
+}
+impl Boat {
+    fn distance(&self, game: &Game, charge: u128) -> u128 {
+        u128::from(self.acceleration) * charge * (u128::from(game.time) - charge)
+    }
+
+    /// Number of charge times that travel strictly further than the record of `game`.
+    ///
+    /// The distance `a * t * (T - t)` beats the record `D` between the roots of
+    /// `a * t^2 - a * T * t + D`, which are found with an integer square root. Returns `None`
+    /// if `acceleration * time` does not fit into a `u64`, as the discriminant would overflow.
+    pub fn ways_to_beat(&self, game: &Game) -> Option<u64> {
+        let (a, time, record) = (
+            u128::from(self.acceleration),
+            u128::from(game.time),
+            u128::from(game.distance),
+        );
+        let a_time = u64::try_from(a * time).ok().map(u128::from)?;
+        // the distance is largest for half the race, and symmetric around it
+        let half = time / 2;
+        if self.distance(game, half) <= record {
+            return Some(0);
+        }
+        let discriminant = a_time * a_time - 4 * a * record;
+        // the estimate can be off by one in either direction because of the rounding, and a
+        // charge time exactly matching the record does not count
+        let mut first = (a_time - discriminant.isqrt()) / (2 * a);
+        while first > 0 && self.distance(game, first - 1) > record {
+            first -= 1;
+        }
+        while self.distance(game, first) <= record {
+            first += 1;
+        }
+        Some((time + 1 - 2 * first) as u64)
     }
 }
 
@@ -54,16 +104,17 @@ fn parse_games(input: &str) -> IResult<&str, Vec<Game>> {
     Ok((input, games))
 }
 
-fn merge_games(input: &[Game]) -> Game {
+/// Joins the digits of all races into one, which may no longer fit into a `u64`.
+fn merge_games(input: &[Game]) -> Result<Game, Overflow> {
     let (time, dist) = input
         .iter()
         .fold((String::new(), String::new()), |(t, d), g| {
             (t + &g.time.to_string(), d + &g.distance.to_string())
         });
-    Game {
-        time: time.parse().unwrap(),
-        distance: dist.parse().unwrap(),
-    }
+    Ok(Game {
+        time: time.parse().map_err(|_| Overflow)?,
+        distance: dist.parse().map_err(|_| Overflow)?,
+    })
 }
 
 fn parse(input: &str) -> Result<Vec<Game>> {
@@ -72,22 +123,39 @@ fn parse(input: &str) -> Result<Vec<Game>> {
         .map(|x| x.1)
 }
 
-fn solve_stage1(input: &[Game]) -> u64 {
-    input.iter().map(|g| g.ways_to_beat()).product()
+fn solve_stage1(input: &[Game]) -> Answer {
+    input
+        .iter()
+        .map(|g| Answer::from(g.ways_to_beat()))
+        .product()
 }
 
-fn solve_stage2(input: &[Game]) -> u64 {
-    let game = merge_games(input);
-    game.ways_to_beat()
+fn solve_stage2(input: &[Game]) -> Answer {
+    Answer::from(merge_games(input).map(|game| game.ways_to_beat()))
+}
+
+/// Solves both stages while reading `input` line by line. The input is only a line of times and
+/// a line of records, so both lines are kept and paired up once the second one has been read.
+pub fn solve_streaming<R: BufRead>(input: R) -> Result<(Answer, Answer)> {
+    let mut lines = input
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.is_empty()));
+    let (Some(times), Some(distances)) = (lines.next(), lines.next()) else {
+        return Err(eyre::eyre!(
+            "Expected a line of times and a line of distances"
+        ));
+    };
+    let input = parse(&format!("{}\n{}", times?, distances?))?;
+    Ok((solve_stage1(&input), solve_stage2(&input)))
 }
 
 pub struct Day6Solver;
 impl AdventOfCodeDay<'_> for Day6Solver {
     type ParsedInput = Vec<Game>;
 
-    type Part1Output = u64;
+    type Part1Output = Answer;
 
-    type Part2Output = u64;
+    type Part2Output = Answer;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
         solve_stage1(input)
@@ -117,4 +185,38 @@ Distance:  9  40  200";
         let input = super::parse(TEST_INPUT).unwrap();
         assert_eq!(super::solve_stage2(&input), 71503);
     }
+    #[test]
+    fn test_streaming() {
+        let (stage1, stage2) = super::solve_streaming(TEST_INPUT.as_bytes()).unwrap();
+        assert_eq!(stage1, 288);
+        assert_eq!(stage2, 71503);
+        assert!(super::solve_streaming("Time: 7 15 30\n".as_bytes()).is_err());
+    }
+    #[test]
+    fn test_merged_overflow() {
+        let input = super::parse("Time: 99999999999 99999999999\nDistance: 1 1").unwrap();
+        assert_eq!(super::merge_games(&input).err(), Some(answer::Overflow));
+    }
+    #[test]
+    fn test_closed_form() {
+        use crate::{Boat, Game};
+
+        for acceleration in 1..4 {
+            let boat = Boat { acceleration };
+            for time in 0..40 {
+                for distance in 0..300 {
+                    let game = Game::new(time, distance);
+                    let expected = (0..=time)
+                        .filter(|&t| acceleration * t * (time - t) > distance)
+                        .count() as u64;
+                    assert_eq!(boat.ways_to_beat(&game), Some(expected));
+                }
+            }
+        }
+        // 10 * 20 only ties the record of 200
+        assert_eq!(Boat::default().ways_to_beat(&Game::new(30, 200)), Some(9));
+        let long = Game::new(u64::MAX, u64::MAX);
+        assert_eq!(Boat::default().ways_to_beat(&long), Some(u64::MAX - 3));
+        assert_eq!(Boat { acceleration: 2 }.ways_to_beat(&long), None);
+    }
 }
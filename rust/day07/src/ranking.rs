@@ -0,0 +1,82 @@
+use color_eyre::Result;
+
+use crate::{Category, Hand, Ruleset};
+
+/// Why a hand ranks above the hand directly below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tiebreak {
+    /// There is no hand below.
+    Lowest,
+    /// It has a stronger category.
+    Category,
+    /// Both have the same category, the card at this index is stronger.
+    Card(usize),
+    /// Both have exactly the same cards, only the bids order them.
+    Duplicate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankedHand<'a> {
+    pub hand: &'a Hand,
+    pub category: Option<&'a Category>,
+    /// 1-based, the weakest hand has rank 1.
+    pub rank: usize,
+    pub winnings: u64,
+    pub tiebreak: Tiebreak,
+}
+
+/// All hands of a game, weakest first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ranking<'a> {
+    pub hands: Vec<RankedHand<'a>>,
+    /// Ranks of hands with identical cards, whose order is not decided by the rules.
+    pub duplicates: Vec<Vec<usize>>,
+}
+
+impl Ranking<'_> {
+    pub fn total_winnings(&self) -> u64 {
+        self.hands.iter().map(|h| h.winnings).sum()
+    }
+}
+
+impl Ruleset {
+    /// Ranks `hands`. Hands with identical cards are ordered by their bid, so the ranking is
+    /// deterministic, and reported in [`Ranking::duplicates`].
+    pub fn ranking<'a>(&'a self, hands: &'a [Hand]) -> Result<Ranking<'a>> {
+        let mut sorted = hands
+            .iter()
+            .map(|h| Ok((self.strength(&h.cards)?, h.bid, h)))
+            .collect::<Result<Vec<_>>>()?;
+        sorted.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+
+        let mut ranking = Ranking {
+            hands: Vec::with_capacity(sorted.len()),
+            duplicates: Vec::new(),
+        };
+        for (i, ((category, ranks), bid, hand)) in sorted.iter().enumerate() {
+            let rank = i + 1;
+            let tiebreak = match i.checked_sub(1).map(|below| &sorted[below].0) {
+                None => Tiebreak::Lowest,
+                Some((below, _)) if below != category => Tiebreak::Category,
+                Some((_, below)) => match ranks.iter().zip(below).position(|(a, b)| a != b) {
+                    Some(card) => Tiebreak::Card(card),
+                    None => Tiebreak::Duplicate,
+                },
+            };
+            if tiebreak == Tiebreak::Duplicate {
+                match ranking.duplicates.last_mut() {
+                    Some(group) if group.last() == Some(&(rank - 1)) => group.push(rank),
+                    _ => ranking.duplicates.push(vec![rank - 1, rank]),
+                }
+            }
+            ranking.hands.push(RankedHand {
+                hand,
+                category: category.map(|c| &self.categories[c]),
+                rank,
+                winnings: rank as u64 * bid,
+                tiebreak,
+            });
+        }
+        Ok(ranking)
+    }
+}
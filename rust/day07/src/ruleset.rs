@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use color_eyre::{eyre, Result};
+
+use crate::Hand;
+
+/// A hand category, given by the sizes of the groups of equal cards it needs. Groups have to
+/// consist of different cards, a full house needs `[3, 2]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Category {
+    pub name: String,
+    pub groups: Vec<usize>,
+}
+
+impl Category {
+    pub fn new(name: &str, groups: &[usize]) -> Category {
+        let mut groups = groups.to_vec();
+        groups.sort_unstable_by(|a, b| b.cmp(a));
+        Category {
+            name: name.to_owned(),
+            groups,
+        }
+    }
+
+    /// Whether cards with the group sizes `counts` (largest first) and `wildcards` can form
+    /// this category.
+    fn fits(&self, counts: &[usize], wildcards: usize) -> bool {
+        // pairing the largest groups with each other needs the fewest wildcards
+        let missing: usize = self
+            .groups
+            .iter()
+            .enumerate()
+            .map(|(i, &group)| group.saturating_sub(counts.get(i).copied().unwrap_or(0)))
+            .sum();
+        missing <= wildcards
+    }
+}
+
+/// The rules of a game of Camel Cards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ruleset {
+    pub hand_size: usize,
+    /// All card labels, weakest first. Wildcards are ranked by their position in here when
+    /// breaking ties.
+    pub ranking: Vec<char>,
+    /// Labels that can stand in for any other card when forming a category.
+    pub wildcards: Vec<char>,
+    /// Hand categories, weakest first.
+    pub categories: Vec<Category>,
+}
+
+impl Ruleset {
+    /// The categories of the puzzle, from high card to five of a kind.
+    pub fn standard_categories() -> Vec<Category> {
+        vec![
+            Category::new("high card", &[]),
+            Category::new("one pair", &[2]),
+            Category::new("two pair", &[2, 2]),
+            Category::new("three of a kind", &[3]),
+            Category::new("full house", &[3, 2]),
+            Category::new("four of a kind", &[4]),
+            Category::new("five of a kind", &[5]),
+        ]
+    }
+
+    /// The rules of stage 1.
+    pub fn standard() -> Ruleset {
+        Ruleset {
+            hand_size: 5,
+            ranking: "23456789TJQKA".chars().collect(),
+            wildcards: Vec::new(),
+            categories: Ruleset::standard_categories(),
+        }
+    }
+
+    /// The rules of stage 2, where jacks become jokers.
+    pub fn jokers() -> Ruleset {
+        Ruleset {
+            ranking: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+            ..Ruleset::standard()
+        }
+    }
+
+    fn rank(&self, card: char) -> Result<usize> {
+        self.ranking
+            .iter()
+            .position(|&c| c == card)
+            .ok_or_else(|| eyre::eyre!("Invalid card: {}", card))
+    }
+
+    fn category_index(&self, cards: &str) -> Result<Option<usize>> {
+        if cards.chars().count() != self.hand_size {
+            return Err(eyre::eyre!(
+                "Hand {} does not have {} cards",
+                cards,
+                self.hand_size
+            ));
+        }
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        let mut wildcards = 0;
+        for card in cards.chars() {
+            self.rank(card)?;
+            if self.wildcards.contains(&card) {
+                wildcards += 1;
+            } else {
+                *counts.entry(card).or_default() += 1;
+            }
+        }
+        let mut counts: Vec<_> = counts.into_values().collect();
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(self
+            .categories
+            .iter()
+            .rposition(|category| category.fits(&counts, wildcards)))
+    }
+
+    /// The strongest category `cards` can form, `None` if they fit no category at all.
+    pub fn category(&self, cards: &str) -> Result<Option<&Category>> {
+        Ok(self.category_index(cards)?.map(|i| &self.categories[i]))
+    }
+
+    /// Sort key of a hand: its category first, then the ranks of its cards in order.
+    pub fn strength(&self, cards: &str) -> Result<(Option<usize>, Vec<usize>)> {
+        let ranks = cards.chars().map(|c| self.rank(c)).collect::<Result<_>>()?;
+        Ok((self.category_index(cards)?, ranks))
+    }
+
+    /// Total winnings of `hands`, each bid multiplied by the rank of its hand.
+    pub fn winnings(&self, hands: &[Hand]) -> Result<u64> {
+        Ok(self.ranking(hands)?.total_winnings())
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write,
+};
+
+use color_eyre::Result;
+
+use crate::Game;
+
+/// The cycle a ghost ends up in when following the instructions from `start`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<'a> {
+    pub start: &'a str,
+    /// Steps before the cycle is entered.
+    pub tail: u64,
+    pub length: u64,
+    /// The distinct nodes visited on the cycle, sorted.
+    pub nodes: Vec<&'a str>,
+}
+
+/// The network as a graph over node indices, with the nodes sorted by name.
+struct Graph<'a> {
+    nodes: Vec<&'a str>,
+    edges: Vec<Vec<usize>>,
+}
+
+impl<'a> Game<'a> {
+    fn graph(&self) -> Graph<'a> {
+        let nodes: Vec<_> = self
+            .mappings
+            .iter()
+            .flat_map(|(&node, &(left, right))| [node, left, right])
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let index: HashMap<_, _> = nodes.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+        let edges = nodes
+            .iter()
+            .map(|node| match self.mappings.get(node) {
+                Some((left, right)) => vec![index[left], index[right]],
+                None => Vec::new(),
+            })
+            .collect();
+        Graph { nodes, edges }
+    }
+
+    /// The strongly connected components of the network, ignoring the instructions. Every
+    /// component comes before the components it leads to.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<&'a str>> {
+        let graph = self.graph();
+        let n = graph.nodes.len();
+
+        // Kosaraju: order the nodes by the time their depth first search finishes...
+        let mut order = Vec::with_capacity(n);
+        let mut visited = vec![false; n];
+        for start in 0..n {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut stack = vec![(start, 0)];
+            while let Some(&(node, i)) = stack.last() {
+                match graph.edges[node].get(i) {
+                    Some(&next) => {
+                        stack.last_mut().unwrap().1 += 1;
+                        if !visited[next] {
+                            visited[next] = true;
+                            stack.push((next, 0));
+                        }
+                    }
+                    None => {
+                        order.push(node);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        // ...then collect the components on the reversed graph, latest finish first
+        let mut reversed = vec![Vec::new(); n];
+        for (node, edges) in graph.edges.iter().enumerate() {
+            for &next in edges {
+                reversed[next].push(node);
+            }
+        }
+        let mut assigned = vec![false; n];
+        let mut components = Vec::new();
+        for &start in order.iter().rev() {
+            if assigned[start] {
+                continue;
+            }
+            assigned[start] = true;
+            let mut component = Vec::new();
+            let mut stack = vec![start];
+            while let Some(node) = stack.pop() {
+                component.push(graph.nodes[node]);
+                for &next in &reversed[node] {
+                    if !assigned[next] {
+                        assigned[next] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
+
+    /// Nodes that cannot be reached from any node matching `is_start`, whatever the
+    /// instructions are.
+    pub fn unreachable_from(&self, is_start: impl Fn(&str) -> bool) -> Vec<&'a str> {
+        let graph = self.graph();
+        let mut stack: Vec<_> = (0..graph.nodes.len())
+            .filter(|&i| is_start(graph.nodes[i]))
+            .collect();
+        let mut reached = vec![false; graph.nodes.len()];
+        stack.iter().for_each(|&i| reached[i] = true);
+        while let Some(node) = stack.pop() {
+            for &next in &graph.edges[node] {
+                if !reached[next] {
+                    reached[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        (0..graph.nodes.len())
+            .filter(|&i| !reached[i])
+            .map(|i| graph.nodes[i])
+            .collect()
+    }
+
+    /// The cycle every node matching `is_start` runs into when following the instructions.
+    pub fn cycles(&self, is_start: impl Fn(&str) -> bool) -> Result<Vec<Cycle<'a>>> {
+        let mut starts: Vec<_> = self.mappings.keys().filter(|n| is_start(n)).collect();
+        starts.sort_unstable();
+        starts
+            .into_iter()
+            .map(|start| {
+                let path = self.ghost_path(start, |_| true)?;
+                let nodes: BTreeSet<_> = path.cycle_hits.iter().map(|h| h.node).collect();
+                Ok(Cycle {
+                    start,
+                    tail: path.tail,
+                    length: path.cycle,
+                    nodes: nodes.into_iter().collect(),
+                })
+            })
+            .collect()
+    }
+
+    /// The network in Graphviz DOT format. Nodes ending in `A` are drawn as boxes, nodes
+    /// ending in `Z` as double circles.
+    pub fn to_dot(&self) -> String {
+        let graph = self.graph();
+        let mut dot = String::from("digraph network {\n");
+        for node in &graph.nodes {
+            let shape = match node.chars().last() {
+                Some('A') => "box",
+                Some('Z') => "doublecircle",
+                _ => "ellipse",
+            };
+            writeln!(dot, "    \"{}\" [shape={}];", node, shape).unwrap();
+        }
+        for node in &graph.nodes {
+            let Some(&(left, right)) = self.mappings.get(node) else {
+                continue;
+            };
+            if left == right {
+                writeln!(dot, "    \"{}\" -> \"{}\" [label=\"L/R\"];", node, left).unwrap();
+            } else {
+                writeln!(dot, "    \"{}\" -> \"{}\" [label=\"L\"];", node, left).unwrap();
+                writeln!(dot, "    \"{}\" -> \"{}\" [label=\"R\"];", node, right).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use color_eyre::{eyre, Result};
+use num_integer::Integer;
+
+use crate::Game;
+
+/// A goal node reached by a ghost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit<'a> {
+    pub time: u64,
+    pub node: &'a str,
+    /// Index into the instructions of the next step.
+    pub instruction: usize,
+}
+
+/// The walk of a single ghost. Its state is the node together with the instruction index, so
+/// it ends up in a cycle of states after at most `nodes * instructions` steps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GhostPath<'a> {
+    pub start: &'a str,
+    /// Steps before the ghost enters its cycle.
+    pub tail: u64,
+    pub cycle: u64,
+    /// Goals reached before entering the cycle.
+    pub tail_hits: Vec<Hit<'a>>,
+    /// Goals reached during the first pass of the cycle, they repeat every `cycle` steps.
+    pub cycle_hits: Vec<Hit<'a>>,
+}
+
+impl GhostPath<'_> {
+    fn hits_at(&self, time: u64) -> bool {
+        if time < self.tail {
+            self.tail_hits.iter().any(|h| h.time == time)
+        } else {
+            let time = self.tail + (time - self.tail) % self.cycle;
+            self.cycle_hits.iter().any(|h| h.time == time)
+        }
+    }
+}
+
+/// Combines `x = a (mod m)` and `x = b (mod n)` for moduli that need not be coprime.
+fn crt((a, m): (i128, i128), (b, n): (i128, i128)) -> Option<(i128, i128)> {
+    let gcd = m.extended_gcd(&n);
+    if (b - a) % gcd.gcd != 0 {
+        return None;
+    }
+    let lcm = m / gcd.gcd * n;
+    let k = ((b - a) / gcd.gcd * gcd.x).rem_euclid(n / gcd.gcd);
+    Some(((a + k * m).rem_euclid(lcm), lcm))
+}
+
+impl<'a> Game<'a> {
+    fn step(&self, node: &str, instruction: usize) -> Result<&'a str> {
+        let (left, right) = self
+            .mappings
+            .get(node)
+            .ok_or_else(|| eyre::eyre!("Unknown node {}", node))?;
+        match self.steps.as_bytes()[instruction] {
+            b'L' => Ok(left),
+            b'R' => Ok(right),
+            c => Err(eyre::eyre!("Invalid instruction {}", c as char)),
+        }
+    }
+
+    /// Walks from `start` until a state repeats, recording every node matching `is_goal`.
+    pub fn ghost_path(
+        &self,
+        start: &'a str,
+        is_goal: impl Fn(&str) -> bool,
+    ) -> Result<GhostPath<'a>> {
+        let mut seen = HashMap::new();
+        let mut hits = Vec::new();
+        let (mut node, mut instruction) = (start, 0);
+        for time in 0.. {
+            if let Some(&first) = seen.get(&(node, instruction)) {
+                let (tail_hits, cycle_hits) = hits.into_iter().partition(|h: &Hit| h.time < first);
+                return Ok(GhostPath {
+                    start,
+                    tail: first,
+                    cycle: time - first,
+                    tail_hits,
+                    cycle_hits,
+                });
+            }
+            seen.insert((node, instruction), time);
+            if is_goal(node) {
+                hits.push(Hit {
+                    time,
+                    node,
+                    instruction,
+                });
+            }
+            node = self.step(node, instruction)?;
+            instruction = (instruction + 1) % self.steps.len();
+        }
+        unreachable!()
+    }
+
+    /// The first time at which all ghosts starting on nodes matching `is_start` stand on
+    /// nodes matching `is_goal` at once, `None` if that never happens.
+    pub fn first_simultaneous(
+        &self,
+        is_start: impl Fn(&str) -> bool,
+        is_goal: impl Fn(&str) -> bool,
+    ) -> Result<Option<u64>> {
+        let paths = self
+            .mappings
+            .keys()
+            .filter(|node| is_start(node))
+            .map(|node| self.ghost_path(node, &is_goal))
+            .collect::<Result<Vec<_>>>()?;
+        let Some(longest) = paths.iter().max_by_key(|p| p.tail) else {
+            return Ok(None);
+        };
+
+        // before every ghost is in its cycle, a solution has to be a tail hit of the ghost
+        // with the longest tail
+        let mut times: Vec<_> = longest.tail_hits.iter().map(|h| h.time).collect();
+        times.sort_unstable();
+        if let Some(time) = times
+            .into_iter()
+            .find(|&t| paths.iter().all(|p| p.hits_at(t)))
+        {
+            return Ok(Some(time));
+        }
+
+        // afterwards each ghost contributes one congruence per hit in its cycle
+        let mut congruences = vec![(0, 1)];
+        for path in &paths {
+            congruences = congruences
+                .iter()
+                .flat_map(|&c| {
+                    path.cycle_hits
+                        .iter()
+                        .filter_map(move |h| crt(c, (h.time.into(), path.cycle.into())))
+                })
+                .collect();
+            congruences.sort_unstable();
+            congruences.dedup();
+        }
+        let tail = i128::from(longest.tail);
+        Ok(congruences
+            .into_iter()
+            .map(|(a, m)| {
+                if a >= tail {
+                    a
+                } else {
+                    a + (tail - a + m - 1) / m * m
+                }
+            })
+            .min()
+            .map(|t| t as u64))
+    }
+}
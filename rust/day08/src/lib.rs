@@ -13,7 +13,11 @@ use nom::{
     sequence::{delimited, separated_pair, terminated},
     IResult,
 };
-use num_integer::Integer;
+
+mod analysis;
+mod ghosts;
+pub use analysis::Cycle;
+pub use ghosts::{GhostPath, Hit};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Game<'a> {
@@ -62,52 +66,11 @@ fn solve_stage1(input: &Game) -> u64 {
     unreachable!()
 }
 
-// This solution is not super generic, since it does not work for the test, which has a tail before it goes into a cycle
-// the real inputs do not have this, so it works
 fn solve_stage2(input: &Game) -> u64 {
-    let mut current: Vec<_> = input
-        .mappings
-        .keys()
-        .filter(|node| node.ends_with('A'))
-        .copied()
-        .collect();
-
-    let mut goals = vec![vec![]; current.len()];
-    let mut cycles = HashMap::new();
-
-    for (i, dir) in input.steps.chars().cycle().enumerate() {
-        for (j, node) in current.iter_mut().enumerate() {
-            let mapping = input.mappings.get(*node).unwrap();
-            match dir {
-                'L' => *node = mapping.0,
-                'R' => *node = mapping.1,
-                _ => unreachable!(),
-            }
-            if node.ends_with('Z') {
-                goals[j].push(((i + 1) as u64, *node));
-                let num_goals = goals[j].len();
-                if num_goals > 2 {
-                    let curr_goal_diff = goals[j][num_goals - 1].0 - goals[j][num_goals - 2].0;
-                    let last_goal_diff = goals[j][num_goals - 2].0 - goals[j][num_goals - 3].0;
-                    if curr_goal_diff == last_goal_diff
-                        && goals[j][num_goals - 1].1 == goals[j][num_goals - 2].1
-                        && goals[j][num_goals - 2].1 == goals[j][num_goals - 3].1
-                    {
-                        cycles.insert(j, (curr_goal_diff, goals[j][0].0));
-                    }
-                }
-            }
-        }
-        // found all cycles
-        if cycles.len() == current.len() {
-            break;
-        }
-    }
-    let residues = cycles.values().map(|x| (x.1 % x.0)).collect::<Vec<_>>();
-    // the lcm below only works if this is true
-    assert!(residues.iter().all(|x| *x == 0));
-    let modulii = cycles.values().map(|x| x.0).collect::<Vec<_>>();
-    modulii.into_iter().reduce(|a, b| a.lcm(&b)).unwrap()
+    input
+        .first_simultaneous(|n| n.ends_with('A'), |n| n.ends_with('Z'))
+        .unwrap()
+        .expect("The ghosts never reach their goals at the same time")
 }
 
 pub struct Day8Solver;
@@ -144,10 +107,52 @@ ZZZ = (ZZZ, ZZZ)";
         let input = super::parse(TEST_INPUT).unwrap();
         assert_eq!(super::solve_stage1(&input), 6);
     }
-    // solution is not generic enough to handle the test input, ironically
-    // #[test]
-    // fn test_stage2() {
-    //     let input = super::parse(TEST_INPUT).unwrap();
-    //     assert_eq!(super::solve_stage2(&input), 6);
-    // }
+    const TEST_INPUT_STAGE2: &str = "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)";
+    #[test]
+    fn test_stage2() {
+        let input = super::parse(TEST_INPUT_STAGE2).unwrap();
+        assert_eq!(super::solve_stage2(&input), 6);
+    }
+    #[test]
+    fn test_ghost_paths() {
+        let input = super::parse(TEST_INPUT_STAGE2).unwrap();
+        let path = input.ghost_path("22A", |n| n.ends_with('Z')).unwrap();
+        assert_eq!((path.tail, path.cycle), (1, 6));
+        let hits: Vec<_> = path.cycle_hits.iter().map(|h| h.time).collect();
+        assert_eq!(hits, [3, 6]);
+
+        // 11Z is reached at even times, 22B one step after each multiple of 3
+        let goals = |n: &str| n == "11Z" || n == "22B";
+        let starts = |n: &str| n.ends_with('A');
+        assert_eq!(input.first_simultaneous(starts, goals).unwrap(), Some(4));
+        let never = |n: &str| n == "11Z" || n == "XXX";
+        assert_eq!(input.first_simultaneous(starts, never).unwrap(), None);
+    }
+    #[test]
+    fn test_analysis() {
+        let input = format!("{}\nQQQ = (QQQ, ZZZ)", TEST_INPUT);
+        let input = super::parse(&input).unwrap();
+        assert_eq!(
+            input.strongly_connected_components(),
+            [vec!["QQQ"], vec!["AAA", "BBB"], vec!["ZZZ"]]
+        );
+        assert_eq!(input.unreachable_from(|n| n == "AAA"), ["QQQ"]);
+
+        let cycles = input.cycles(|n| n == "AAA").unwrap();
+        assert_eq!((cycles[0].tail, cycles[0].length), (6, 3));
+        assert_eq!(cycles[0].nodes, ["ZZZ"]);
+
+        let dot = input.to_dot();
+        assert!(dot.contains("\"AAA\" -> \"BBB\" [label=\"L/R\"];"));
+        assert!(dot.contains("\"BBB\" -> \"ZZZ\" [label=\"R\"];"));
+    }
 }
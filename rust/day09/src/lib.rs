@@ -1,4 +1,4 @@
-use std::cell::OnceCell;
+use std::{cell::OnceCell, fmt::Display, io::BufRead};
 
 use aoc_traits::AdventOfCodeDay;
 use color_eyre::{
@@ -13,43 +13,77 @@ use nom::{
     sequence::tuple,
     IResult,
 };
+use num_bigint::BigInt;
+use num_traits::Zero;
+
+/// Returned when the values are not described by a polynomial of a degree lower than their
+/// count, so the differences never become all zeros.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct NotPolynomial {
+    pub values: usize,
+}
+
+impl Display for NotPolynomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the differences of {} values never become all zeros",
+            self.values
+        )
+    }
+}
+
+impl std::error::Error for NotPolynomial {}
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Game {
     values: Vec<i64>,
-    sequences: OnceCell<Vec<Vec<i64>>>,
+    /// The first value of every row of the difference table, down to the first row of zeros.
+    differences: OnceCell<Result<Vec<BigInt>, NotPolynomial>>,
 }
 
 impl Game {
-    fn seqs(&self) -> &Vec<Vec<i64>> {
-        self.sequences.get_or_init(|| {
-            let mut seqs = vec![self.values.clone()];
-            while !seqs.last().unwrap().iter().all(|&x| x == 0) {
-                let new_seq = seqs
-                    .last()
-                    .unwrap()
-                    .as_slice()
-                    .windows(2)
-                    .map(|x| x[1] - x[0])
-                    .collect();
-                seqs.push(new_seq);
-            }
-            seqs
-        })
+    fn differences(&self) -> Result<&[BigInt], NotPolynomial> {
+        self.differences
+            .get_or_init(|| {
+                let mut row: Vec<BigInt> = self.values.iter().map(|&v| v.into()).collect();
+                let mut leading = Vec::new();
+                while !row.iter().all(|x| x.is_zero()) {
+                    leading.push(row[0].clone());
+                    row = row.windows(2).map(|x| &x[1] - &x[0]).collect();
+                }
+                // an empty row is all zeros as well, but says nothing about the values
+                if row.is_empty() {
+                    return Err(NotPolynomial {
+                        values: self.values.len(),
+                    });
+                }
+                Ok(leading)
+            })
+            .as_deref()
+            .map_err(|&e| e)
     }
-    fn extend(&self) -> i64 {
-        self.seqs()
-            .iter()
-            .rev()
-            .skip(1)
-            .fold(0, |acc, x| acc + x.last().unwrap())
+
+    /// The value at position `k` of the sequence, where 0 is the first given value. Uses
+    /// Newton's forward difference formula, `f(k) = sum(binomial(k, j) * differences[j])`.
+    pub fn extrapolate(&self, k: i64) -> Result<BigInt, NotPolynomial> {
+        let k = BigInt::from(k);
+        let mut binomial = BigInt::from(1);
+        let mut value = BigInt::zero();
+        for (j, difference) in self.differences()?.iter().enumerate() {
+            value += &binomial * difference;
+            // binomial(k, j + 1) is an integer, so the division is exact
+            binomial = binomial * (&k - j) / (j + 1);
+        }
+        Ok(value)
     }
-    fn extend_back(&self) -> i64 {
-        self.seqs()
-            .iter()
-            .rev()
-            .skip(1)
-            .fold(0, |acc, x| x.first().unwrap() - acc)
+
+    fn extend(&self) -> Result<i64> {
+        let value = self.extrapolate(self.values.len() as i64)?;
+        Ok(i64::try_from(value)?)
+    }
+    fn extend_back(&self) -> Result<i64> {
+        Ok(i64::try_from(self.extrapolate(-1)?)?)
     }
 }
 
@@ -57,14 +91,15 @@ fn parse_i64(input: &str) -> IResult<&str, i64> {
     map_res(recognize(tuple((opt(tag("-")), digit1))), str::parse::<i64>)(input)
 }
 
+fn parse_game(input: &str) -> IResult<&str, Game> {
+    map(separated_list1(space1, parse_i64), |values| Game {
+        values,
+        differences: OnceCell::new(),
+    })(input)
+}
+
 fn parse_games(input: &str) -> IResult<&str, Vec<Game>> {
-    let (input, hands) = all_consuming(separated_list1(
-        line_ending,
-        map(separated_list1(space1, parse_i64), |values| Game {
-            values,
-            sequences: OnceCell::new(),
-        }),
-    ))(input)?;
+    let (input, hands) = all_consuming(separated_list1(line_ending, parse_game))(input)?;
 
     Ok((input, hands))
 }
@@ -75,14 +110,31 @@ fn parse(input: &str) -> Result<Vec<Game>> {
         .map(|x| x.1)
 }
 
-fn solve_stage1(input: &[Game]) -> i64 {
+fn solve_stage1(input: &[Game]) -> Result<i64> {
     input.iter().map(|g| g.extend()).sum()
 }
 
-fn solve_stage2(input: &[Game]) -> i64 {
+fn solve_stage2(input: &[Game]) -> Result<i64> {
     input.iter().map(|g| g.extend_back()).sum()
 }
 
+/// Solves both stages while reading `input` one sequence per line. Only the current sequence
+/// and its differences are kept, so memory depends on the longest history, not on the report.
+pub fn solve_streaming<R: BufRead>(input: R) -> Result<(i64, i64)> {
+    input
+        .lines()
+        .enumerate()
+        .try_fold((0, 0), |(stage1, stage2), (i, line)| {
+            let line = line?;
+            if line.is_empty() {
+                return Ok((stage1, stage2));
+            }
+            let (_, game) = all_consuming(parse_game)(&line)
+                .map_err(|e| eyre::eyre!("Failed to parse line {}: {}", i + 1, e))?;
+            Ok((stage1 + game.extend()?, stage2 + game.extend_back()?))
+        })
+}
+
 pub struct Day9Solver;
 impl AdventOfCodeDay<'_> for Day9Solver {
     type ParsedInput = Vec<Game>;
@@ -92,11 +144,11 @@ impl AdventOfCodeDay<'_> for Day9Solver {
     type Part2Output = i64;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
-        solve_stage1(input)
+        solve_stage1(input).unwrap()
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        solve_stage2(input)
+        solve_stage2(input).unwrap()
     }
 
     fn parse_input(input: &'_ str) -> Self::ParsedInput {
@@ -113,11 +165,36 @@ mod tests {
     #[test]
     fn test_stage1() {
         let input = super::parse(TEST_INPUT).unwrap();
-        assert_eq!(super::solve_stage1(&input), 114);
+        assert_eq!(super::solve_stage1(&input).unwrap(), 114);
     }
     #[test]
     fn test_stage2() {
         let input = super::parse(TEST_INPUT).unwrap();
-        assert_eq!(super::solve_stage2(&input), 2);
+        assert_eq!(super::solve_stage2(&input).unwrap(), 2);
+    }
+    #[test]
+    fn test_streaming() {
+        assert_eq!(
+            super::solve_streaming(TEST_INPUT.as_bytes()).unwrap(),
+            (114, 2)
+        );
+    }
+    #[test]
+    fn test_extrapolate() {
+        let input = super::parse(TEST_INPUT).unwrap();
+        let game = &input[2];
+        assert_eq!(game.extrapolate(0).unwrap(), 10.into());
+        assert_eq!(game.extrapolate(6).unwrap(), 68.into());
+        assert_eq!(game.extrapolate(-1).unwrap(), 5.into());
+        // the values follow a cubic, which quickly leaves the range of i64
+        let far = game.extrapolate(i64::MAX).unwrap();
+        assert!(i64::try_from(far).is_err());
+
+        let input = super::parse("1 2 4 8").unwrap();
+        assert_eq!(
+            input[0].extrapolate(4),
+            Err(super::NotPolynomial { values: 4 })
+        );
+        assert!(super::solve_streaming("1 2 4 8".as_bytes()).is_err());
     }
 }
@@ -1,4 +1,4 @@
-use std::{str::FromStr, vec};
+use std::str::FromStr;
 
 use aoc_traits::AdventOfCodeDay;
 use color_eyre::{
@@ -6,14 +6,17 @@ use color_eyre::{
     Result,
 };
 
+mod network;
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Game {
     grid: Vec<Vec<Cell>>,
     start: (usize, usize),
+    start_shape: Cell,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum Cell {
+pub enum Cell {
     Empty,
     Start,
     NS,
@@ -68,7 +71,7 @@ impl FromStr for Game {
     type Err = Error;
 
     fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
-        let mut start = (0, 0);
+        let mut start = None;
         let grid = s
             .lines()
             .enumerate()
@@ -79,8 +82,9 @@ impl FromStr for Game {
                         '.' => Ok(Cell::Empty),
                         '-' => Ok(Cell::WE),
                         '|' => Ok(Cell::NS),
+                        'S' if start.is_some() => Err(eyre::eyre!("More than one start tile")),
                         'S' => {
-                            start = (i, j);
+                            start = Some((i, j));
                             Ok(Cell::Start)
                         }
                         'L' => Ok(Cell::NE),
@@ -92,155 +96,88 @@ impl FromStr for Game {
                     .collect::<Result<Vec<_>, _>>()
             })
             .collect::<Result<Vec<_>, _>>()?;
+        let start = start.ok_or_else(|| eyre::eyre!("Missing start tile"))?;
+        let start_shape = network::infer_start_shape(&grid, start)?;
 
-        Ok(Game { grid, start })
-    }
-}
-
-fn solve_stage1(input: &Game) -> i64 {
-    let mut loop_len = 0;
-    for offset in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
-        loop_len = 0;
-        let mut cur = (
-            (input.start.0 as isize + offset.0) as usize,
-            (input.start.1 as isize + offset.1) as usize,
-        );
-        let mut from = input.start;
-        while let Some(next) = input.next_cell(from, cur) {
-            from = cur;
-            cur = next;
-            loop_len += 1;
-        }
-        if cur == input.start {
-            break;
+        let game = Game {
+            grid,
+            start,
+            start_shape,
+        };
+        if game.walk_loop().is_none() {
+            return Err(eyre::eyre!(
+                "The pipes leaving the start tile do not form a loop"
+            ));
         }
+        Ok(game)
     }
-    (loop_len + 1) / 2
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum Phase2Cell {
-    Wall,
-    Outside,
-    Unknown,
-}
-
-fn solve_stage2(input: &Game) -> i64 {
-    let mut loop_path = vec![];
-    for offset in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
-        let mut from = input.start;
-        let mut cur = (
-            (input.start.0 as isize + offset.0) as usize,
-            (input.start.1 as isize + offset.1) as usize,
-        );
-        loop_path = vec![from, cur];
-        while let Some(next) = input.next_cell(from, cur) {
+impl Game {
+    /// Follows the pipes from the start tile, returning `None` if they do not lead back to it.
+    fn walk_loop(&self) -> Option<Vec<(usize, usize)>> {
+        let (mut from, mut cur) = (self.start, self.first_step());
+        let mut loop_path = vec![from, cur];
+        while let Some(next) = self.next_cell(from, cur) {
             from = cur;
             cur = next;
             loop_path.push(cur);
         }
-        if cur == input.start {
-            break;
-        }
-    }
-    // now that we have the loop, find all enclosed cells
-    // extend the playing field by a factor of 2, to have spaces in between
-    let (x_dim, y_dim) = (input.grid.len() * 2 + 1, input.grid[0].len() * 2 + 1);
-    let mut grid = vec![vec![Phase2Cell::Unknown; y_dim]; x_dim];
-    // mark all walls
-    for x in loop_path.windows(2) {
-        let (x1, y1) = (x[0].0 * 2 + 1, x[0].1 * 2 + 1);
-        let (x3, y3) = (x[1].0 * 2 + 1, x[1].1 * 2 + 1);
-        let (x2, y2) = ((x1 + x3) / 2, (y1 + y3) / 2);
-        grid[x1][y1] = Phase2Cell::Wall;
-        grid[x2][y2] = Phase2Cell::Wall;
-        grid[x3][y3] = Phase2Cell::Wall;
+        (cur == self.start).then_some(loop_path)
     }
-    //print_grid(&grid);
-    //print_grid_ext(&grid);
-    // mark all border cells as outside
-    #[allow(clippy::needless_range_loop)] // i find the loops to be clearer
-    for i in 0..x_dim {
-        if grid[i][0] == Phase2Cell::Unknown {
-            grid[i][0] = Phase2Cell::Outside;
-        }
-        if grid[i][y_dim - 1] == Phase2Cell::Unknown {
-            grid[i][y_dim - 1] = Phase2Cell::Outside;
-        }
+
+    /// The tiles of the main loop in order, starting and ending at the start tile.
+    pub fn loop_path(&self) -> Vec<(usize, usize)> {
+        self.walk_loop().expect("the loop is checked while parsing")
     }
-    for i in 0..y_dim {
-        if grid[0][i] == Phase2Cell::Unknown {
-            grid[0][i] = Phase2Cell::Outside;
-        }
-        if grid[x_dim - 1][i] == Phase2Cell::Unknown {
-            grid[x_dim - 1][i] = Phase2Cell::Outside;
-        }
+
+    /// Number of tiles enclosed by the loop. The shoelace formula gives the area of the
+    /// polygon through the centers of the loop tiles, and Pick's theorem `A = i + b/2 - 1`
+    /// turns that into the number of interior points `i`, with the `b` loop tiles on the
+    /// boundary.
+    pub fn enclosed_count(&self) -> i64 {
+        let path = self.loop_path();
+        let twice_area = path
+            .windows(2)
+            .map(|w| {
+                let ((r1, c1), (r2, c2)) = (w[0], w[1]);
+                (r1 * c2) as i64 - (r2 * c1) as i64
+            })
+            .sum::<i64>()
+            .abs();
+        let boundary = (path.len() - 1) as i64;
+        (twice_area - boundary + 2) / 2
     }
-    // propagate outside cells
-    let mut changed = true;
-    while changed {
-        changed = false;
-        // we handled the border already, its either outside or wall
-        for i in 1..x_dim - 1 {
-            for j in 1..y_dim - 1 {
-                if grid[i][j] == Phase2Cell::Unknown {
-                    // if an unknown cell is touching an inside cell, it's inside
-                    // same for outside
-                    let mut touching_outside = false;
-                    for offset in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
-                        let (x, y) = (
-                            (i as isize + offset.0) as usize,
-                            (j as isize + offset.1) as usize,
-                        );
-                        if grid[x][y] == Phase2Cell::Outside {
-                            touching_outside = true;
-                        }
-                    }
-                    if touching_outside {
-                        grid[i][j] = Phase2Cell::Outside;
-                        changed = true;
+
+    /// All tiles enclosed by the loop, row by row. Scanning a row from the left, every loop
+    /// tile with a pipe going north toggles between outside and inside.
+    pub fn enclosed_tiles(&self) -> Vec<(usize, usize)> {
+        let on_loop = self.on_loop();
+        let mut enclosed = Vec::new();
+        for (row, tiles) in on_loop.iter().enumerate() {
+            let mut inside = false;
+            for (col, &is_loop) in tiles.iter().enumerate() {
+                if !is_loop {
+                    if inside {
+                        enclosed.push((row, col));
                     }
+                    continue;
+                }
+                if matches!(self.shape((row, col)), Cell::NS | Cell::NE | Cell::NW) {
+                    inside = !inside;
                 }
             }
         }
+        enclosed
     }
-    //print_grid(&grid);
-    //print_grid_ext(&grid);
-    // all unknown cells are inside, but filter out the extended cells
-    grid.iter()
-        .skip(1)
-        .step_by(2)
-        .flat_map(|x| x.iter().skip(1).step_by(2))
-        .filter(|&&x| x == Phase2Cell::Unknown)
-        .count() as i64
 }
 
-#[allow(unused)]
-fn print_grid_ext(grid: &[Vec<Phase2Cell>]) {
-    for row in grid {
-        for cell in row {
-            match cell {
-                Phase2Cell::Wall => print!("#"),
-                Phase2Cell::Outside => print!("O"),
-                Phase2Cell::Unknown => print!("?"),
-            }
-        }
-        println!();
-    }
+fn solve_stage1(input: &Game) -> i64 {
+    (input.loop_path().len() as i64 - 1) / 2
 }
 
-#[allow(unused)]
-fn print_grid(grid: &[Vec<Phase2Cell>]) {
-    for row in grid.iter().skip(1).step_by(2) {
-        for cell in row.iter().skip(1).step_by(2) {
-            match cell {
-                Phase2Cell::Wall => print!("#"),
-                Phase2Cell::Outside => print!("O"),
-                Phase2Cell::Unknown => print!("?"),
-            }
-        }
-        println!();
-    }
+fn solve_stage2(input: &Game) -> i64 {
+    input.enclosed_count()
 }
 
 pub struct Day10Solver;
@@ -266,7 +203,7 @@ impl AdventOfCodeDay<'_> for Day10Solver {
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use std::{cmp::Ordering, str::FromStr};
 
     use crate::Game;
 
@@ -334,4 +271,114 @@ L7JLJL-JLJLJL--JLJ.L";
         let input = Game::from_str(TEST_INPUT).unwrap();
         assert_eq!(super::solve_stage2(&input), 10);
     }
+    /// Builds a random loop around a region with a jagged top and bottom edge, surrounded by
+    /// random pipes that are not part of it.
+    fn generate_maze(seed: u64) -> String {
+        let mut state = seed;
+        let mut random = |n: usize| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize % n
+        };
+        let (width, depth) = (2 + random(12), 1 + random(6));
+        let tops: Vec<_> = (0..width).map(|_| 1 + random(depth)).collect();
+        let bottoms: Vec<_> = (0..width).map(|_| 2 + depth + random(depth)).collect();
+        let mut corners = vec![(tops[0], 1)];
+        for (x, &top) in tops.iter().enumerate() {
+            corners.extend([(top, x + 1), (top, x + 2)]);
+        }
+        for x in (0..width).rev() {
+            corners.extend([(bottoms[x], x + 2), (bottoms[x], x + 1)]);
+        }
+        corners.push((tops[0], 1));
+
+        let mut path = vec![corners[0]];
+        for &target in &corners[1..] {
+            while *path.last().unwrap() != target {
+                let (row, col) = *path.last().unwrap();
+                path.push(match (row.cmp(&target.0), col.cmp(&target.1)) {
+                    (Ordering::Less, _) => (row + 1, col),
+                    (Ordering::Greater, _) => (row - 1, col),
+                    (_, Ordering::Less) => (row, col + 1),
+                    _ => (row, col - 1),
+                });
+            }
+        }
+
+        let mut grid: Vec<Vec<char>> = (0..2 * depth + 3)
+            .map(|_| {
+                (0..width + 3)
+                    .map(|_| ['.', '|', '-', 'L', 'J', '7', 'F'][random(7)])
+                    .collect()
+            })
+            .collect();
+        for i in 1..path.len() {
+            let (prev, cur) = (path[i - 1], path[i]);
+            let next = path[if i + 1 < path.len() { i + 1 } else { 1 }];
+            let north = prev.0 + 1 == cur.0 || next.0 + 1 == cur.0;
+            let south = prev.0 == cur.0 + 1 || next.0 == cur.0 + 1;
+            let west = prev.1 + 1 == cur.1 || next.1 + 1 == cur.1;
+            grid[cur.0][cur.1] = match (north, south, west) {
+                (true, true, _) => '|',
+                (true, _, true) => 'J',
+                (true, _, _) => 'L',
+                (_, true, true) => '7',
+                (_, true, _) => 'F',
+                _ => '-',
+            };
+        }
+        // only the loop may connect to the start tile
+        let (row, col) = path[0];
+        grid[row - 1][col] = '.';
+        grid[row][col - 1] = '.';
+        grid[row][col] = 'S';
+        grid.iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    #[test]
+    fn test_enclosed_tiles() {
+        const TEST_INPUT: &str = "...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+...........";
+        let input = Game::from_str(TEST_INPUT).unwrap();
+        assert_eq!(input.enclosed_tiles(), [(6, 2), (6, 3), (6, 7), (6, 8)]);
+
+        for seed in 0..200 {
+            let maze = generate_maze(seed);
+            let input = Game::from_str(&maze).unwrap();
+            assert_eq!(
+                input.enclosed_count(),
+                input.enclosed_tiles().len() as i64,
+                "{}",
+                maze
+            );
+        }
+    }
+    #[test]
+    fn test_network() {
+        const TEST_INPUT: &str = "-L|F7
+7S-7|
+L|7||
+-L-J|
+L|-JF";
+        let input = Game::from_str(TEST_INPUT).unwrap();
+        assert_eq!(input.start_shape(), super::Cell::SE);
+        assert_eq!(input.render_loop(), ".....\n.┌─┐.\n.│.│.\n.└─┘.\n.....\n");
+        assert_eq!(input.dangling_pipes().len(), 17);
+        assert_eq!(input.dangling_pipes()[..2], [(0, 0), (0, 1)]);
+
+        assert!(Game::from_str("S-.").is_err());
+        assert!(Game::from_str(".|.\n-S-\n.|.").is_err());
+        assert!(Game::from_str("F7\nLJ").is_err());
+        assert!(Game::from_str(".|.\n.S-\n...").is_err());
+    }
 }
@@ -0,0 +1,130 @@
+use color_eyre::{eyre, Result};
+
+use crate::{Cell, Game};
+
+const DIRECTIONS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+impl Cell {
+    /// Whether the pipe connects to its neighbour in direction `(row, col)`.
+    fn connects(self, direction: (isize, isize)) -> bool {
+        matches!(
+            (self, direction),
+            (Cell::NS | Cell::NE | Cell::NW, (-1, 0))
+                | (Cell::NS | Cell::SE | Cell::SW, (1, 0))
+                | (Cell::WE | Cell::NW | Cell::SW, (0, -1))
+                | (Cell::WE | Cell::NE | Cell::SE, (0, 1))
+        )
+    }
+
+    /// The pipe connecting the two given directions, which are in the order of [`DIRECTIONS`].
+    fn joining(first: (isize, isize), second: (isize, isize)) -> Cell {
+        match (first, second) {
+            ((-1, 0), (1, 0)) => Cell::NS,
+            ((-1, 0), (0, -1)) => Cell::NW,
+            ((-1, 0), (0, 1)) => Cell::NE,
+            ((1, 0), (0, -1)) => Cell::SW,
+            ((1, 0), (0, 1)) => Cell::SE,
+            _ => Cell::WE,
+        }
+    }
+
+    fn box_drawing(self) -> char {
+        match self {
+            Cell::Empty => '.',
+            Cell::Start => 'S',
+            Cell::NS => '│',
+            Cell::WE => '─',
+            Cell::NE => '└',
+            Cell::NW => '┘',
+            Cell::SE => '┌',
+            Cell::SW => '┐',
+        }
+    }
+}
+
+fn neighbour(
+    grid: &[Vec<Cell>],
+    (row, col): (usize, usize),
+    (dr, dc): (isize, isize),
+) -> Option<Cell> {
+    let (row, col) = (row.checked_add_signed(dr)?, col.checked_add_signed(dc)?);
+    grid.get(row)?.get(col).copied()
+}
+
+/// Infers the pipe hidden below the start tile from the neighbours connecting to it.
+pub(crate) fn infer_start_shape(grid: &[Vec<Cell>], start: (usize, usize)) -> Result<Cell> {
+    let connected: Vec<_> = DIRECTIONS
+        .into_iter()
+        .filter(|&(dr, dc)| {
+            neighbour(grid, start, (dr, dc)).is_some_and(|cell| cell.connects((-dr, -dc)))
+        })
+        .collect();
+    match connected[..] {
+        [first, second] => Ok(Cell::joining(first, second)),
+        _ => Err(eyre::eyre!(
+            "Start tile at {:?} has {} connecting neighbours instead of 2",
+            start,
+            connected.len()
+        )),
+    }
+}
+
+impl Game {
+    /// The shape of the pipe below the start tile.
+    pub fn start_shape(&self) -> Cell {
+        self.start_shape
+    }
+
+    /// The shape of the tile at `row`/`col`, with the start tile replaced by its pipe.
+    pub(crate) fn shape(&self, (row, col): (usize, usize)) -> Cell {
+        match self.grid[row][col] {
+            Cell::Start => self.start_shape,
+            cell => cell,
+        }
+    }
+
+    /// The first step of the loop away from the start tile.
+    pub(crate) fn first_step(&self) -> (usize, usize) {
+        let (dr, dc) = DIRECTIONS
+            .into_iter()
+            .find(|&d| self.start_shape.connects(d))
+            .unwrap();
+        (
+            self.start.0.wrapping_add_signed(dr),
+            self.start.1.wrapping_add_signed(dc),
+        )
+    }
+
+    /// Pipes that are not part of the main loop, row by row.
+    pub fn dangling_pipes(&self) -> Vec<(usize, usize)> {
+        let on_loop = self.on_loop();
+        (0..self.grid.len())
+            .flat_map(|row| (0..self.grid[row].len()).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.grid[row][col] != Cell::Empty && !on_loop[row][col])
+            .collect()
+    }
+
+    pub(crate) fn on_loop(&self) -> Vec<Vec<bool>> {
+        let mut on_loop = vec![vec![false; self.grid[0].len()]; self.grid.len()];
+        for (row, col) in self.loop_path() {
+            on_loop[row][col] = true;
+        }
+        on_loop
+    }
+
+    /// Draws the main loop with box-drawing characters, all other tiles become `.`.
+    pub fn render_loop(&self) -> String {
+        let on_loop = self.on_loop();
+        let mut out = String::new();
+        for (row, tiles) in on_loop.iter().enumerate() {
+            for (col, &is_loop) in tiles.iter().enumerate() {
+                out.push(match is_loop {
+                    true => self.shape((row, col)).box_drawing(),
+                    false => '.',
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
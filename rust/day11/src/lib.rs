@@ -1,11 +1,26 @@
 use std::str::FromStr;
 
+use answer::Answer;
 use aoc_traits::AdventOfCodeDay;
+use color_eyre::{eyre::eyre, Result};
 
 pub struct Space {
     galaxies: Vec<(usize, usize)>,
-    empty_rows: Vec<bool>,
-    empty_cols: Vec<bool>,
+    /// Number of empty rows above each row.
+    empty_rows_before: Vec<u64>,
+    /// Number of empty columns left of each column.
+    empty_cols_before: Vec<u64>,
+}
+
+/// Running count of the `true` entries before each position.
+fn prefix_counts(empty: impl Iterator<Item = bool>) -> Vec<u64> {
+    empty
+        .scan(0, |count, empty| {
+            let before = *count;
+            *count += u64::from(empty);
+            Some(before)
+        })
+        .collect()
 }
 
 impl FromStr for Space {
@@ -22,10 +37,7 @@ impl FromStr for Space {
             }
         }
         let (_x_dim, y_dim) = (input.len(), input[0].len());
-        let empty_rows = input
-            .iter()
-            .map(|row| row.iter().all(|c| *c == '.'))
-            .collect::<Vec<_>>();
+        let empty_rows = input.iter().map(|row| row.iter().all(|c| *c == '.'));
         let empty_cols = input.iter().fold(vec![true; y_dim], |acc, row| {
             row.iter()
                 .enumerate()
@@ -34,74 +46,149 @@ impl FromStr for Space {
         });
         Ok(Space {
             galaxies,
-            empty_rows,
-            empty_cols,
+            empty_rows_before: prefix_counts(empty_rows),
+            empty_cols_before: prefix_counts(empty_cols.into_iter()),
         })
     }
 }
 
-fn solve_stage1(input: &Space) -> u64 {
-    input
-        .galaxies
-        .iter()
-        .enumerate()
-        .flat_map(|(i, x)| {
-            input.galaxies.iter().skip(i + 1).map(|y| {
-                let (start_x, end_x) = if x.0 < y.0 { (x.0, y.0) } else { (y.0, x.0) };
-                let (start_y, end_y) = if x.1 < y.1 { (x.1, y.1) } else { (y.1, x.1) };
-                let x_boost = input.empty_rows[start_x..end_x]
-                    .iter()
-                    .filter(|x| **x)
-                    .count();
-                let y_boost = input.empty_cols[start_y..end_y]
-                    .iter()
-                    .filter(|x| **x)
-                    .count();
-                end_x - start_x + end_y - start_y + x_boost + y_boost
+impl Space {
+    /// The number of lines each empty line grows by, `expansion` must keep at least the line
+    /// itself.
+    fn growth(expansion: u64) -> Result<u64> {
+        expansion
+            .checked_sub(1)
+            .ok_or_else(|| eyre!("Empty lines cannot be removed entirely"))
+    }
+
+    /// Coordinate after every empty line before it has grown by `growth` lines.
+    fn expanded(coord: usize, empty_before: &[u64], growth: u64) -> Answer {
+        Answer::from(coord as u64) + Answer::from(empty_before[coord]) * Answer::from(growth)
+    }
+
+    /// Sum of the distances between all pairs of `coords` along one axis. After sorting, the
+    /// i-th coordinate is the larger one in exactly i pairs, so every pair is covered by the
+    /// running sum of the smaller coordinates.
+    fn axis_total(mut coords: Vec<usize>, empty_before: &[u64], growth: u64) -> Answer {
+        coords.sort_unstable();
+        let mut smaller = Answer::from(0);
+        let mut total = Answer::from(0);
+        for (i, coord) in coords.into_iter().enumerate() {
+            let coord = Space::expanded(coord, empty_before, growth);
+            total = total + coord.clone() * Answer::from(i as u64) - smaller.clone();
+            smaller = smaller + coord;
+        }
+        total
+    }
+
+    pub fn galaxies(&self) -> &[(usize, usize)] {
+        &self.galaxies
+    }
+
+    /// Sum of the distances between all pairs of galaxies, with every empty row and column
+    /// replaced by `expansion` of them. Takes O(g log g) for g galaxies.
+    pub fn total_distance(&self, expansion: u64) -> Result<Answer> {
+        let growth = Space::growth(expansion)?;
+        let rows = self.galaxies.iter().map(|g| g.0).collect();
+        let cols = self.galaxies.iter().map(|g| g.1).collect();
+        Ok(Space::axis_total(rows, &self.empty_rows_before, growth)
+            + Space::axis_total(cols, &self.empty_cols_before, growth))
+    }
+
+    fn grown_distance(&self, a: usize, b: usize, growth: u64) -> Answer {
+        let ((ar, ac), (br, bc)) = (self.galaxies[a], self.galaxies[b]);
+        let axis = |a: usize, b: usize, empty_before: &[u64]| {
+            Space::expanded(a.max(b), empty_before, growth)
+                - Space::expanded(a.min(b), empty_before, growth)
+        };
+        axis(ar, br, &self.empty_rows_before) + axis(ac, bc, &self.empty_cols_before)
+    }
+
+    /// Distance between the galaxies with index `a` and `b`.
+    pub fn distance(&self, a: usize, b: usize, expansion: u64) -> Result<Answer> {
+        Ok(self.grown_distance(a, b, Space::growth(expansion)?))
+    }
+
+    /// For every galaxy, the index of the closest other galaxy and the distance to it.
+    ///
+    /// The galaxies are sorted by row, and the search around each galaxy stops as soon as the
+    /// row difference alone exceeds the closest distance found. That is fast for scattered
+    /// galaxies, but still O(g²) in the worst case, like a single column of galaxies.
+    pub fn nearest(&self, expansion: u64) -> Result<Vec<Option<(usize, Answer)>>> {
+        let growth = Space::growth(expansion)?;
+        // below 2^128 for any coordinate, the sums of two differences are checked
+        let coord = |c: usize, empty_before: &[u64]| {
+            c as u128 + u128::from(empty_before[c]) * u128::from(growth)
+        };
+        let mut expanded: Vec<_> = self
+            .galaxies
+            .iter()
+            .enumerate()
+            .map(|(i, &(r, c))| {
+                (
+                    coord(r, &self.empty_rows_before),
+                    coord(c, &self.empty_cols_before),
+                    i,
+                )
             })
-        })
-        .sum::<usize>() as u64
+            .collect();
+        expanded.sort_unstable();
+        let too_far = || eyre!("Distances do not fit into 128 bits");
+        let mut nearest = vec![None; expanded.len()];
+        for (pos, &(row, col, a)) in expanded.iter().enumerate() {
+            let mut best: Option<(u128, usize)> = None;
+            // returns whether galaxies further away along this direction can still be closer
+            let mut visit = |&(other_row, other_col, b): &(u128, u128, usize)| -> Result<bool> {
+                let rows = row.abs_diff(other_row);
+                if best.is_some_and(|(d, _)| rows > d) {
+                    return Ok(false);
+                }
+                let d = rows
+                    .checked_add(col.abs_diff(other_col))
+                    .ok_or_else(too_far)?;
+                if best.is_none_or(|best| (d, b) < best) {
+                    best = Some((d, b));
+                }
+                Ok(true)
+            };
+            for other in &expanded[pos + 1..] {
+                if !visit(other)? {
+                    break;
+                }
+            }
+            for other in expanded[..pos].iter().rev() {
+                if !visit(other)? {
+                    break;
+                }
+            }
+            nearest[a] = best.map(|(_, b)| (b, self.grown_distance(a, b, growth)));
+        }
+        Ok(nearest)
+    }
 }
 
-fn solve_stage2(input: &Space) -> u64 {
-    input
-        .galaxies
-        .iter()
-        .enumerate()
-        .flat_map(|(i, x)| {
-            input.galaxies.iter().skip(i + 1).map(|y| {
-                let (start_x, end_x) = if x.0 < y.0 { (x.0, y.0) } else { (y.0, x.0) };
-                let (start_y, end_y) = if x.1 < y.1 { (x.1, y.1) } else { (y.1, x.1) };
-                let x_boost = input.empty_rows[start_x..end_x]
-                    .iter()
-                    .filter(|x| **x)
-                    .count()
-                    * 999_999;
-                let y_boost = input.empty_cols[start_y..end_y]
-                    .iter()
-                    .filter(|x| **x)
-                    .count()
-                    * 999_999;
-                end_x - start_x + end_y - start_y + x_boost + y_boost
-            })
-        })
-        .sum::<usize>() as u64
+fn solve_stage1(input: &Space) -> Result<Answer> {
+    input.total_distance(2)
+}
+
+fn solve_stage2(input: &Space) -> Result<Answer> {
+    input.total_distance(1_000_000)
 }
 
 pub struct Day11Solver;
 impl AdventOfCodeDay<'_> for Day11Solver {
     type ParsedInput = Space;
 
-    type Part1Output = u64;
+    type Part1Output = Answer;
 
-    type Part2Output = u64;
+    type Part2Output = Answer;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
-        solve_stage1(input)
+        solve_stage1(input).unwrap()
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        solve_stage2(input)
+        solve_stage2(input).unwrap()
     }
 
     fn parse_input(input: &str) -> Self::ParsedInput {
@@ -128,12 +215,35 @@ mod tests {
     #[test]
     fn test_stage1() {
         let input = Day11Solver::parse_input(TEST_INPUT);
-        assert_eq!(super::solve_stage1(&input), 374);
+        assert_eq!(super::solve_stage1(&input).unwrap(), 374);
+    }
+    #[test]
+    fn test_stage2() {
+        let input = Day11Solver::parse_input(TEST_INPUT);
+        assert_eq!(input.total_distance(10).unwrap(), 1030);
+        assert_eq!(input.total_distance(100).unwrap(), 8410);
+        assert!(input.total_distance(0).is_err());
+    }
+    #[test]
+    fn test_queries() {
+        let input = Day11Solver::parse_input(TEST_INPUT);
+        // galaxies 5 and 9 in the puzzle's numbering
+        assert_eq!(input.distance(4, 8, 2).unwrap(), 9);
+        assert_eq!(input.distance(8, 4, 2).unwrap(), 9);
+        assert!(input.distance(4, 8, 0).is_err());
+        assert!(input.nearest(0).is_err());
+        for expansion in [1, 2, 10, 1_000_000] {
+            let nearest = input.nearest(expansion).unwrap();
+            for (a, found) in nearest.into_iter().enumerate() {
+                let (b, distance) = found.unwrap();
+                let closest = (0..input.galaxies().len())
+                    .filter(|&other| other != a)
+                    .map(|other| input.distance(a, other, expansion).unwrap().get().unwrap())
+                    .min();
+                assert_eq!(Some(distance.get().unwrap()), closest);
+                assert_ne!(a, b);
+            }
+        }
+        assert_eq!(input.nearest(2).unwrap()[0].as_ref().unwrap().0, 1);
     }
-    // #[test]
-    // only works for the 100 factor
-    // fn test_stage2() {
-    //     let input = Day11Solver::parse_input(TEST_INPUT);
-    //     assert_eq!(super::solve_stage2(&input), 8410);
-    // }
 }
@@ -0,0 +1,189 @@
+use rand::Rng;
+
+use crate::{Field, Spring, State};
+
+const START: State = State { started: 0, run: 0 };
+
+impl Field {
+    /// `completions[i][s]` is the number of ways to fill the springs from position `i` on,
+    /// starting in the state with index `s`.
+    fn completions(&self) -> Vec<Vec<u64>> {
+        let mut completions = vec![vec![0; self.state_count()]; self.springs.len() + 1];
+        for (s, state) in self.states() {
+            completions[self.springs.len()][s] = u64::from(self.accepts(state));
+        }
+        for i in (0..self.springs.len()).rev() {
+            for (s, state) in self.states() {
+                completions[i][s] = self.springs[i]
+                    .options()
+                    .iter()
+                    .filter_map(|&option| self.step(state, option))
+                    .map(|next| completions[i + 1][self.index(next)])
+                    // only unreachable states can exceed the total number of arrangements
+                    .fold(0, u64::saturating_add);
+            }
+        }
+        completions
+    }
+
+    /// `prefixes[i][s]` is the number of ways to fill the first `i` springs ending in the
+    /// state with index `s`.
+    fn prefixes(&self) -> Vec<Vec<u64>> {
+        let mut prefixes = vec![vec![0u64; self.state_count()]; self.springs.len() + 1];
+        prefixes[0][self.index(START)] = 1;
+        for (i, spring) in self.springs.iter().enumerate() {
+            for (s, state) in self.states() {
+                for &option in spring.options() {
+                    if let Some(next) = self.step(state, option) {
+                        let next = self.index(next);
+                        prefixes[i + 1][next] =
+                            prefixes[i + 1][next].saturating_add(prefixes[i][s]);
+                    }
+                }
+            }
+        }
+        prefixes
+    }
+
+    /// Lazily yields every way to replace the unknown springs so that the row matches its
+    /// chunks, in lexicographic order with working before broken springs.
+    pub fn arrangements_iter(&self) -> Arrangements<'_> {
+        Arrangements {
+            field: self,
+            completions: self.completions(),
+            choices: Vec::new(),
+            states: vec![START],
+            started: false,
+        }
+    }
+
+    /// Picks one of the valid arrangements uniformly at random, or `None` if there is none.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Vec<Spring>> {
+        let completions = self.completions();
+        let mut state = START;
+        let mut row = Vec::with_capacity(self.springs.len());
+        if completions[0][self.index(state)] == 0 {
+            return None;
+        }
+        for (i, spring) in self.springs.iter().enumerate() {
+            let mut pick = rng.gen_range(0..completions[i][self.index(state)]);
+            for &option in spring.options() {
+                let Some(next) = self.step(state, option) else {
+                    continue;
+                };
+                let ways = completions[i + 1][self.index(next)];
+                if pick < ways {
+                    row.push(option);
+                    state = next;
+                    break;
+                }
+                pick -= ways;
+            }
+        }
+        Some(row)
+    }
+
+    /// The fraction of valid arrangements in which each spring is broken, or `None` if there is
+    /// no valid arrangement.
+    pub fn broken_probabilities(&self) -> Option<Vec<f64>> {
+        let completions = self.completions();
+        let total = completions[0][self.index(START)];
+        if total == 0 {
+            return None;
+        }
+        let prefixes = self.prefixes();
+        let probabilities = (0..self.springs.len())
+            .map(|i| {
+                let broken: u128 = self
+                    .states()
+                    .filter(|_| self.springs[i] != Spring::Working)
+                    .filter_map(|(s, state)| {
+                        let next = self.step(state, Spring::Broken)?;
+                        Some(
+                            u128::from(prefixes[i][s])
+                                * u128::from(completions[i + 1][self.index(next)]),
+                        )
+                    })
+                    .sum();
+                broken as f64 / total as f64
+            })
+            .collect();
+        Some(probabilities)
+    }
+}
+
+/// Iterator over the valid arrangements of a [`Field`], see [`Field::arrangements_iter`].
+pub struct Arrangements<'a> {
+    field: &'a Field,
+    completions: Vec<Vec<u64>>,
+    /// Index into [`Spring::options`] chosen for each position so far.
+    choices: Vec<usize>,
+    /// State before each position, plus the one after the last choice.
+    states: Vec<State>,
+    started: bool,
+}
+
+impl Arrangements<'_> {
+    /// The first option at position `i`, starting from option `from`, that still leads to a
+    /// valid arrangement.
+    fn viable(&self, i: usize, from: usize) -> Option<(usize, State)> {
+        let field = self.field;
+        field.springs[i]
+            .options()
+            .iter()
+            .enumerate()
+            .skip(from)
+            .find_map(|(choice, &option)| {
+                let next = field.step(self.states[i], option)?;
+                (self.completions[i + 1][field.index(next)] > 0).then_some((choice, next))
+            })
+    }
+
+    /// Takes the first viable option at every remaining position.
+    fn descend(&mut self) {
+        for i in self.choices.len()..self.field.springs.len() {
+            let (choice, next) = self
+                .viable(i, 0)
+                .expect("states with completions always have a viable option");
+            self.choices.push(choice);
+            self.states.push(next);
+        }
+    }
+
+    /// Moves to the next viable option at the deepest position that has one.
+    fn backtrack(&mut self) -> bool {
+        while let Some(choice) = self.choices.pop() {
+            self.states.pop();
+            if let Some((choice, next)) = self.viable(self.choices.len(), choice + 1) {
+                self.choices.push(choice);
+                self.states.push(next);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl Iterator for Arrangements<'_> {
+    type Item = Vec<Spring>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            if self.completions[0][self.field.index(START)] == 0 {
+                return None;
+            }
+        } else if !self.backtrack() {
+            return None;
+        }
+        self.descend();
+        Some(
+            self.field
+                .springs
+                .iter()
+                .zip(&self.choices)
+                .map(|(spring, &choice)| spring.options()[choice])
+                .collect(),
+        )
+    }
+}
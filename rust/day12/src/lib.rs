@@ -1,15 +1,14 @@
-use std::{
-    collections::HashMap,
-    fmt::Display,
-    iter,
-    str::FromStr,
-    sync::{Mutex, OnceLock},
-};
+use std::{fmt::Display, io::BufRead, iter, str::FromStr};
 
 use aoc_traits::AdventOfCodeDay;
+use color_eyre::eyre;
+use rayon::prelude::*;
+
+mod arrangement;
+pub use arrangement::Arrangements;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Spring {
+pub enum Spring {
     Working,
     Broken,
     Unknown,
@@ -29,175 +28,148 @@ impl Display for Spring {
 pub struct Field {
     springs: Vec<Spring>,
     chunks: Vec<usize>,
+    /// Number of distinct run lengths of a state, see [`Field::index`].
+    width: usize,
 }
 impl FromStr for Field {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (springs, chunks) = s.split_once(' ').unwrap();
+        let (springs, chunks) = s.split_once(' ').ok_or(())?;
         let springs = springs
             .chars()
             .map(|c| match c {
-                '.' => Spring::Working,
-                '#' => Spring::Broken,
-                '?' => Spring::Unknown,
-                _ => unreachable!(),
+                '.' => Ok(Spring::Working),
+                '#' => Ok(Spring::Broken),
+                '?' => Ok(Spring::Unknown),
+                _ => Err(()),
             })
-            .collect();
-        let chunks = chunks.split(',').map(|x| x.parse().unwrap()).collect();
-        Ok(Field { springs, chunks })
+            .collect::<Result<_, _>>()?;
+        // a chunk has at least one broken spring, a run of 0 means being outside of a chunk
+        let chunks = chunks
+            .split(',')
+            .map(|x| x.parse().ok().filter(|&x| x > 0).ok_or(()))
+            .collect::<Result<_, _>>()?;
+        Ok(Field::new(springs, chunks))
     }
 }
 
-type Memoizer = Mutex<HashMap<(Vec<Spring>, Vec<usize>), u64>>;
+/// Progress through the chunks after a prefix of the row: `started` chunks have begun and the
+/// last of them has `run` broken springs so far. A `run` of 0 means the prefix ends outside of
+/// any chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct State {
+    started: usize,
+    run: usize,
+}
 
-fn memoizer() -> &'static Memoizer {
-    static ARRAY: OnceLock<Memoizer> = OnceLock::new();
-    ARRAY.get_or_init(|| Mutex::new(HashMap::new()))
+impl Spring {
+    /// The concrete springs this one can stand for.
+    fn options(self) -> &'static [Spring] {
+        match self {
+            Spring::Working => &[Spring::Working],
+            Spring::Broken => &[Spring::Broken],
+            Spring::Unknown => &[Spring::Working, Spring::Broken],
+        }
+    }
 }
 
-fn num_valid_wrapper(springs: &mut [Spring], chunks: &[usize]) -> u64 {
-    if springs.is_empty() && chunks.is_empty() {
-        return 1;
+impl Field {
+    pub fn springs(&self) -> &[Spring] {
+        &self.springs
     }
-    if springs.is_empty() && !chunks.is_empty() {
-        return 0;
+
+    pub fn chunks(&self) -> &[usize] {
+        &self.chunks
     }
-    if let Some(val) = memoizer()
-        .lock()
-        .unwrap()
-        .get(&(springs.to_vec(), chunks.to_vec()))
-        .copied()
-    {
-        return val;
+
+    fn new(springs: Vec<Spring>, chunks: Vec<usize>) -> Field {
+        let width = chunks.iter().max().copied().unwrap_or(0) + 1;
+        Field {
+            springs,
+            chunks,
+            width,
+        }
     }
-    let (s, c) = (springs.to_vec(), chunks.to_vec());
-    let res = num_valid(springs, chunks);
-    memoizer().lock().unwrap().insert((s, c), res);
-    res
-}
 
-fn num_valid(springs: &mut [Spring], chunks: &[usize]) -> u64 {
-    if springs.is_empty() && chunks.is_empty() {
-        return 1;
+    fn state_count(&self) -> usize {
+        (self.chunks.len() + 1) * self.width
     }
-    if springs.is_empty() && !chunks.is_empty() {
-        return 0;
+
+    fn index(&self, state: State) -> usize {
+        state.started * self.width + state.run
     }
-    // shortcut if len is exactly as needed to fit all chunks + seperator
-    if springs.len() == chunks.iter().sum::<usize>() + chunks.len() - 1 {
-        let mut i = 0;
-        for chunk in chunks {
-            for _ in 0..*chunk {
-                if springs[i] == Spring::Working {
-                    return 0;
-                }
-                i += 1;
-            }
-            if i < springs.len() && springs[i] == Spring::Broken {
-                return 0;
-            }
-            i += 1;
+
+    fn state(&self, index: usize) -> State {
+        State {
+            started: index / self.width,
+            run: index % self.width,
         }
+    }
 
-        return 1;
+    /// All states that can occur, together with their index.
+    fn states(&self) -> impl Iterator<Item = (usize, State)> + '_ {
+        (0..self.state_count())
+            .map(|i| (i, self.state(i)))
+            .filter(|(_, s)| s.run == 0 || (s.started > 0 && s.run <= self.chunks[s.started - 1]))
     }
-    match springs[0] {
-        // remove . prefixes
-        Spring::Working => return num_valid_wrapper(&mut springs[1..], chunks),
-        // # prefixes must be the size of the chunk
-        Spring::Broken => {
-            if chunks.is_empty() {
-                return 0;
-            }
-            if chunks[0] > springs.len() {
-                return 0;
-            }
-            for spring in springs.iter().take(chunks[0]) {
-                // must be broken or unknown
-                if spring == &Spring::Working {
-                    return 0;
-                }
-            }
-            let springs = &mut springs[chunks[0]..];
-            let chunks = &chunks[1..];
-            if springs.is_empty() {
-                if chunks.is_empty() {
-                    return 1;
-                } else {
-                    return 0;
-                }
-            }
-            if chunks.is_empty() {
-                return if springs.iter().all(|x| *x != Spring::Broken) {
-                    1
-                } else {
-                    0
-                };
-            }
-            if springs[0] == Spring::Broken {
-                return 0;
+
+    /// Whether the current chunk, if any, is complete.
+    fn closed(&self, state: State) -> bool {
+        state.run == 0 || state.run == self.chunks[state.started - 1]
+    }
+
+    /// Appends a concrete `spring` to the prefix, if the result can still match the chunks.
+    fn step(&self, state: State, spring: Spring) -> Option<State> {
+        match spring {
+            Spring::Working => self.closed(state).then_some(State {
+                started: state.started,
+                run: 0,
+            }),
+            Spring::Broken if state.run == 0 => {
+                (state.started < self.chunks.len()).then_some(State {
+                    started: state.started + 1,
+                    run: 1,
+                })
             }
-            return num_valid_wrapper(&mut springs[1..], chunks);
+            Spring::Broken => (state.run < self.chunks[state.started - 1]).then_some(State {
+                started: state.started,
+                run: state.run + 1,
+            }),
+            Spring::Unknown => unreachable!("only concrete springs can be appended"),
         }
-        _ => (), // handle unknwns later
-    };
-    let springs_len = springs.len();
-    if springs_len > 1 {
-        match springs[springs.len() - 1] {
-            // remove . suffixes
-            Spring::Working => return num_valid_wrapper(&mut springs[..springs_len - 1], chunks),
-            Spring::Broken => {
-                if chunks.is_empty() {
-                    return 0;
-                }
-                if chunks[chunks.len() - 1] > springs.len() {
-                    return 0;
-                }
-                for i in 0..chunks[chunks.len() - 1] {
-                    // must be broken or unknown
-                    if springs[springs_len - 1 - i] == Spring::Working {
-                        return 0;
-                    }
-                }
-                let springs = &mut springs[..springs_len - chunks[chunks.len() - 1]];
-                let chunks = &chunks[..chunks.len() - 1];
-                if springs.is_empty() {
-                    if chunks.is_empty() {
-                        return 1;
-                    } else {
-                        return 0;
+    }
+
+    /// Whether a whole row ending in `state` matches the chunks.
+    fn accepts(&self, state: State) -> bool {
+        state.started == self.chunks.len() && self.closed(state)
+    }
+
+    /// Number of ways to replace the unknown springs so that the row matches its chunks.
+    ///
+    /// Runs a dynamic program over the position in the row, the chunk index and the length of
+    /// the current run, keeping only the counts of the current position.
+    pub fn arrangements(&self) -> u64 {
+        let mut counts = vec![0; self.state_count()];
+        counts[self.index(State { started: 0, run: 0 })] = 1;
+        for spring in &self.springs {
+            let mut next = vec![0; counts.len()];
+            for (i, &count) in counts.iter().enumerate().filter(|(_, &c)| c > 0) {
+                for &option in spring.options() {
+                    if let Some(state) = self.step(self.state(i), option) {
+                        next[self.index(state)] += count;
                     }
                 }
-                if chunks.is_empty() {
-                    return if springs.iter().all(|x| *x != Spring::Broken) {
-                        1
-                    } else {
-                        0
-                    };
-                }
-                if springs.last().unwrap() == &Spring::Broken {
-                    return 0;
-                }
-                let springs_len = springs.len();
-                return num_valid_wrapper(&mut springs[..springs_len - 1], chunks);
             }
-            _ => (),
+            counts = next;
         }
+        counts
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| self.accepts(self.state(i)))
+            .map(|(_, count)| count)
+            .sum()
     }
-
-    // split unkowns into two branches, starting with the one that has the larger chunk at its side
-    let mut new = springs.to_vec();
-    if chunks.first().unwrap() <= chunks.last().unwrap() {
-        assert!(springs[0] == Spring::Unknown);
-        new[0] = Spring::Working;
-        springs[0] = Spring::Broken;
-    } else {
-        assert!(springs[springs.len() - 1] == Spring::Unknown);
-        new[springs.len() - 1] = Spring::Working;
-        springs[springs.len() - 1] = Spring::Broken;
-    }
-    num_valid_wrapper(&mut new, chunks) + num_valid_wrapper(springs, chunks)
 }
 
 impl Field {
@@ -217,26 +189,37 @@ impl Field {
             .take(self.chunks.len() * 5)
             .copied()
             .collect();
-        Field { springs, chunks }
+        Field::new(springs, chunks)
     }
 }
 
 fn solve_stage1(input: &[Field]) -> u64 {
-    input
-        .iter()
-        .map(|x| {
-            let mut springs = x.springs.clone();
-            num_valid(&mut springs, &x.chunks)
-        })
-        .sum()
+    input.iter().map(Field::arrangements).sum()
 }
 
 fn solve_stage2(input: &[Field]) -> u64 {
-    let mut unfolded = input.iter().map(|x| x.unfold()).collect::<Vec<_>>();
-    unfolded
-        .iter_mut()
-        .map(|x| num_valid(&mut x.springs, &x.chunks))
-        .sum()
+    input.par_iter().map(|x| x.unfold().arrangements()).sum()
+}
+
+/// Solves both stages while reading `input` one row per line. Each row is counted on its own
+/// and dropped, so memory grows with the longest unfolded row instead of the number of rows.
+pub fn solve_streaming<R: BufRead>(input: R) -> eyre::Result<(u64, u64)> {
+    input
+        .lines()
+        .enumerate()
+        .try_fold((0, 0), |(stage1, stage2), (i, line)| {
+            let line = line?;
+            if line.is_empty() {
+                return Ok((stage1, stage2));
+            }
+            let field: Field = line
+                .parse()
+                .map_err(|_| eyre::eyre!("Failed to parse line {}", i + 1))?;
+            Ok((
+                stage1 + field.arrangements(),
+                stage2 + field.unfold().arrangements(),
+            ))
+        })
 }
 
 pub struct Day12Solver;
@@ -282,4 +265,83 @@ mod tests {
         let input = Day12Solver::parse_input(TEST_INPUT);
         assert_eq!(super::solve_stage2(&input), 525152);
     }
+    #[test]
+    fn test_streaming() {
+        assert_eq!(
+            super::solve_streaming(TEST_INPUT.as_bytes()).unwrap(),
+            (21, 525152)
+        );
+    }
+    #[test]
+    fn test_arrangements() {
+        let counts: Vec<_> = Day12Solver::parse_input(TEST_INPUT)
+            .iter()
+            .map(|x| x.arrangements())
+            .collect();
+        assert_eq!(counts, [1, 4, 1, 1, 4, 10]);
+        let edge_cases: Vec<_> = ["# 1", ". 1", "?? 1", "#?# 3", "#.# 1", "... 1"]
+            .iter()
+            .map(|x| x.parse::<crate::Field>().unwrap().arrangements())
+            .collect();
+        assert_eq!(edge_cases, [1, 0, 2, 1, 0, 0]);
+        assert!("?# 0".parse::<crate::Field>().is_err());
+        assert!("?# 1,0,1".parse::<crate::Field>().is_err());
+        assert!(super::solve_streaming("?# 0".as_bytes()).is_err());
+    }
+    #[test]
+    fn test_arrangements_iter() {
+        let render =
+            |row: Vec<crate::Spring>| row.iter().map(|s| s.to_string()).collect::<String>();
+        let field: crate::Field = ".??..??...?##. 1,1,3".parse().unwrap();
+        let rows: Vec<_> = field.arrangements_iter().map(render).collect();
+        assert_eq!(
+            rows,
+            [
+                "..#...#...###.",
+                "..#..#....###.",
+                ".#....#...###.",
+                ".#...#....###.",
+            ]
+        );
+        for field in Day12Solver::parse_input(TEST_INPUT) {
+            let unfolded = field.unfold();
+            assert_eq!(
+                field.arrangements_iter().count() as u64,
+                field.arrangements()
+            );
+            assert_eq!(
+                unfolded.arrangements_iter().take(1000).count() as u64,
+                unfolded.arrangements().min(1000)
+            );
+        }
+        let impossible: crate::Field = "#.# 1".parse().unwrap();
+        assert_eq!(impossible.arrangements_iter().count(), 0);
+        assert!(impossible.sample(&mut rand::thread_rng()).is_none());
+        assert!(impossible.broken_probabilities().is_none());
+    }
+    #[test]
+    fn test_sampling() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let field: crate::Field = "?###???????? 3,2,1".parse().unwrap();
+        let arrangements: Vec<_> = field.arrangements_iter().collect();
+        let mut rng = StdRng::seed_from_u64(12);
+        let mut hits = vec![0; arrangements.len()];
+        for _ in 0..10_000 {
+            let row = field.sample(&mut rng).unwrap();
+            hits[arrangements.iter().position(|a| *a == row).unwrap()] += 1;
+        }
+        // each of the 10 arrangements is expected 1000 times
+        assert!(hits.iter().all(|&h| (850..1150).contains(&h)), "{:?}", hits);
+
+        let probabilities = field.broken_probabilities().unwrap();
+        for (i, p) in probabilities.iter().enumerate() {
+            let broken = arrangements
+                .iter()
+                .filter(|a| a[i] == crate::Spring::Broken)
+                .count();
+            assert!((p - broken as f64 / arrangements.len() as f64).abs() < 1e-12);
+        }
+        assert_eq!(probabilities[0], 0.0);
+        assert_eq!(probabilities[1], 1.0);
+    }
 }
@@ -1,6 +1,7 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, io::BufRead};
 
 use aoc_traits::AdventOfCodeDay;
+use color_eyre::{eyre, Result};
 
 fn hash(input: &str) -> u8 {
     input
@@ -8,6 +9,80 @@ fn hash(input: &str) -> u8 {
         .fold(0, |acc, x| (acc.wrapping_add(x).wrapping_mul(17)))
 }
 
+/// Splits a step into its label and the focal length for `=` operations, `None` for `-`.
+fn parse_step(step: &str) -> Result<(&str, Option<u8>)> {
+    let invalid = || eyre::eyre!("Invalid step {:?}", step);
+    let op = step.find(['=', '-']).ok_or_else(invalid)?;
+    match (step.as_bytes()[op], &step[op + 1..]) {
+        (b'=', focal_length) => Ok((
+            &step[..op],
+            Some(focal_length.parse().map_err(|_| invalid())?),
+        )),
+        (_, "") => Ok((&step[..op], None)),
+        _ => Err(invalid()),
+    }
+}
+
+fn apply_step<L: AsRef<str>>(boxes: &mut [VecDeque<(L, u8)>], label: L, focal_length: Option<u8>) {
+    let hash = hash(label.as_ref());
+    let lenses = &mut boxes[hash as usize];
+    match focal_length {
+        Some(num) => {
+            if let Some(i) = lenses
+                .iter_mut()
+                .find(|(l, _)| l.as_ref() == label.as_ref())
+            {
+                i.1 = num;
+            } else {
+                lenses.push_back((label, num));
+            }
+        }
+        None => {
+            if let Some(i) = lenses
+                .iter()
+                .position(|(l, _)| l.as_ref() == label.as_ref())
+            {
+                lenses.remove(i);
+            }
+        }
+    }
+}
+
+fn focusing_power<L>(boxes: Vec<VecDeque<(L, u8)>>) -> u64 {
+    boxes
+        .into_iter()
+        .enumerate()
+        .map(|(i, x)| {
+            x.into_iter()
+                .enumerate()
+                .map(|(j, (_, x))| x as u64 * (i + 1) as u64 * (j + 1) as u64)
+                .sum::<u64>()
+        })
+        .sum()
+}
+
+/// Solves both stages while reading `input` one comma-separated step at a time, so the
+/// (single-line) initialization sequence never has to be held in memory as a whole.
+pub fn solve_streaming<R: BufRead>(input: R) -> Result<(u64, u64)> {
+    let mut boxes = vec![VecDeque::<(String, u8)>::new(); 256];
+    let (mut stage1, mut offset) = (0, 0);
+    for (i, step) in input.split(b',').enumerate() {
+        let step = step?;
+        let start = offset;
+        offset += step.len() + 1;
+        let step = String::from_utf8(step)?;
+        let step = step.trim_end_matches(['\r', '\n']);
+        if step.is_empty() {
+            continue;
+        }
+        stage1 += hash(step) as u64;
+        let (label, focal_length) = parse_step(step)
+            .map_err(|e| eyre::eyre!("Failed to parse step {} at byte {}: {}", i + 1, start, e))?;
+        apply_step(&mut boxes, label.to_string(), focal_length);
+    }
+    Ok((stage1, focusing_power(boxes)))
+}
+
 pub struct Day15Solver;
 impl<'a> AdventOfCodeDay<'a> for Day15Solver {
     type ParsedInput = &'a str;
@@ -23,36 +98,10 @@ impl<'a> AdventOfCodeDay<'a> for Day15Solver {
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
         let mut boxes = vec![VecDeque::<(&str, u8)>::new(); 256];
         input.split(',').for_each(|x| {
-            let op = x.find(|c| c == '=' || c == '-').unwrap();
-            let label = &x[..op];
-            let hash = hash(label);
-            match x.as_bytes()[op] {
-                b'=' => {
-                    let num: u8 = x[op + 1..].parse().unwrap();
-                    if let Some(i) = boxes[hash as usize].iter_mut().find(|(l, _)| l == &label) {
-                        i.1 = num;
-                    } else {
-                        boxes[hash as usize].push_back((label, num));
-                    }
-                }
-                b'-' => {
-                    if let Some(i) = boxes[hash as usize].iter().position(|(l, _)| l == &label) {
-                        boxes[hash as usize].remove(i);
-                    }
-                }
-                _ => unreachable!(),
-            }
+            let (label, focal_length) = parse_step(x).unwrap();
+            apply_step(&mut boxes, label, focal_length);
         });
-        boxes
-            .into_iter()
-            .enumerate()
-            .map(|(i, x)| {
-                x.into_iter()
-                    .enumerate()
-                    .map(|(j, (_, x))| x as u64 * (i + 1) as u64 * (j + 1) as u64)
-                    .sum::<u64>()
-            })
-            .sum()
+        focusing_power(boxes)
     }
 
     fn parse_input(input: &'a str) -> Self::ParsedInput {
@@ -76,4 +125,18 @@ mod tests {
         let input = Day15Solver::parse_input(TEST_INPUT);
         assert_eq!(Day15Solver::solve_part2(&input), 145);
     }
+    #[test]
+    fn test_streaming() {
+        assert_eq!(
+            super::solve_streaming(TEST_INPUT.as_bytes()).unwrap(),
+            (1320, 145)
+        );
+        let error = super::solve_streaming("rn=1,cm,qp=3".as_bytes()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to parse step 2 at byte 5: Invalid step \"cm\""
+        );
+        assert!(super::solve_streaming("rn=x".as_bytes()).is_err());
+        assert!(super::solve_streaming("rn-1".as_bytes()).is_err());
+    }
 }
@@ -1,5 +1,6 @@
 use std::{collections::HashMap, str::FromStr};
 
+use answer::Answer;
 use aoc_traits::AdventOfCodeDay;
 use nom::{
     bytes::complete::tag,
@@ -82,8 +83,11 @@ impl PartCollection {
         result
     }
 
-    fn variants(&self) -> u64 {
-        self.xmas.iter().map(|x| x.end - x.start).product()
+    fn variants(&self) -> Answer {
+        self.xmas
+            .iter()
+            .map(|x| Answer::from(x.end - x.start))
+            .product()
     }
 }
 
@@ -184,9 +188,9 @@ pub struct Day19Solver;
 impl AdventOfCodeDay<'_> for Day19Solver {
     type ParsedInput = Game;
 
-    type Part1Output = u64;
+    type Part1Output = Answer;
 
-    type Part2Output = u64;
+    type Part2Output = Answer;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
         let Game { rules, parts } = input;
@@ -203,7 +207,7 @@ impl AdventOfCodeDay<'_> for Day19Solver {
                     }
                 }
             })
-            .map(|part| part.xmas.iter().sum::<u64>())
+            .map(|part| part.xmas.iter().copied().map(Answer::from).sum())
             .sum()
     }
 
@@ -3,6 +3,7 @@ use std::{
     str::FromStr,
 };
 
+use answer::Answer;
 use aoc_traits::AdventOfCodeDay;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -93,7 +94,7 @@ fn solve_stage1(input: &Grid, steps: usize) -> u64 {
     dists.values().filter(|&&x| x % 2 == 0).count() as u64
 }
 
-fn solve_stage2(input: &Grid, steps: usize) -> u64 {
+fn solve_stage2(input: &Grid, steps: usize) -> Answer {
     let offset = input.start.0;
     let grid_size = input.dims.0;
     assert!((steps - offset) % grid_size == 0);
@@ -102,37 +103,41 @@ fn solve_stage2(input: &Grid, steps: usize) -> u64 {
         .map(|i| {
             let small_step = offset + i * grid_size;
             let dists = input.get_dist(small_step);
-            dists
+            let count = dists
                 .values()
                 .filter(|&&x| x % 2 == (small_step & 1))
-                .count() as u64
+                .count();
+            Answer::from(count as u64)
         })
         .collect();
 
     // first derivative
     let diffs = reachable
         .windows(2)
-        .map(|x| x[1] - x[0])
+        .map(|x| x[1].clone() - x[0].clone())
         .collect::<Vec<_>>();
     // second derivative
-    let diffs2 = diffs.windows(2).map(|x| x[1] - x[0]).collect::<Vec<_>>();
+    let diffs2 = diffs
+        .windows(2)
+        .map(|x| x[1].clone() - x[0].clone())
+        .collect::<Vec<_>>();
 
     // is constant
-    assert!(diffs2.iter().all(|&x| x == diffs2[0]));
+    assert!(diffs2.iter().all(|x| x == &diffs2[0]));
 
     // to lazy to get a formula for this, do it the pyramid way
-    let mut v = vec![0; num_total_grids];
-    v[0] = diffs[0];
+    let mut v = vec![Answer::from(0); num_total_grids];
+    v[0] = diffs[0].clone();
     for i in 1..num_total_grids {
-        v[i] = v[i - 1] + diffs2[0];
+        v[i] = v[i - 1].clone() + diffs2[0].clone();
     }
-    let mut total_reachable = vec![0; num_total_grids + 1];
-    total_reachable[0] = reachable[0];
+    let mut total_reachable = vec![Answer::from(0); num_total_grids + 1];
+    total_reachable[0] = reachable[0].clone();
     for i in 1..num_total_grids + 1 {
-        total_reachable[i] = total_reachable[i - 1] + v[i - 1];
+        total_reachable[i] = total_reachable[i - 1].clone() + v[i - 1].clone();
     }
 
-    *total_reachable.last().unwrap()
+    total_reachable.pop().unwrap()
 }
 
 pub struct Day21Solver;
@@ -141,7 +146,7 @@ impl AdventOfCodeDay<'_> for Day21Solver {
 
     type Part1Output = u64;
 
-    type Part2Output = u64;
+    type Part2Output = Answer;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
         solve_stage1(input, 64)
@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+use answer::{Overflow, SignedAnswer};
 use aoc_traits::AdventOfCodeDay;
 use nom::{
     bytes::complete::tag,
@@ -102,7 +103,7 @@ fn solve_stage1(input: &[Hailstone], limits: (i64, i64)) -> u64 {
     count
 }
 
-fn solve_stage2(input: &[Hailstone]) -> i64 {
+fn solve_stage2(input: &[Hailstone]) -> SignedAnswer {
     let x1 = input[0].pos.0 as f64;
     let y1 = input[0].pos.1 as f64;
     let z1 = input[0].pos.2 as f64;
@@ -253,10 +254,18 @@ fn solve_stage2(input: &[Hailstone]) -> i64 {
         - matrix[0][5] * vz0)
         / matrix[0][0];
 
-    let x0 = x0.round() as i64;
-    let y0 = y0.round() as i64;
-    let z0 = z0.round() as i64;
-    x0 + y0 + z0
+    exact(x0) + exact(y0) + exact(z0)
+}
+
+/// Rounds a coordinate of the rock. Above 2^53 a `f64` cannot tell neighbouring integers apart,
+/// so such a coordinate is reported as [`Overflow`] instead of a silently wrong answer.
+fn exact(value: f64) -> SignedAnswer {
+    const LIMIT: f64 = (1u64 << f64::MANTISSA_DIGITS) as f64;
+    if value.abs() <= LIMIT {
+        SignedAnswer::from_f64(value.round())
+    } else {
+        SignedAnswer::from(Err(Overflow))
+    }
 }
 
 pub struct Day24Solver;
@@ -265,7 +274,7 @@ impl AdventOfCodeDay<'_> for Day24Solver {
 
     type Part1Output = u64;
 
-    type Part2Output = i64;
+    type Part2Output = SignedAnswer;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
         solve_stage1(input, (200000000000000, 400000000000000))
@@ -301,4 +310,27 @@ mod tests {
         let input = Day24Solver::parse_input(TEST_INPUT);
         assert_eq!(super::solve_stage2(&input), 47);
     }
+    /// The example hailstones moved by 2^60, the rock is too far out for a `f64`.
+    fn far_hailstones() -> Vec<crate::Hailstone> {
+        let offset = 1i64 << 60;
+        Day24Solver::parse_input(TEST_INPUT)
+            .into_iter()
+            .map(|mut h| {
+                h.pos = (h.pos.0 + offset, h.pos.1 + offset, h.pos.2 + offset);
+                h
+            })
+            .collect()
+    }
+    #[cfg(any(feature = "checked", feature = "bigint"))]
+    #[test]
+    fn test_stage2_precision() {
+        let answer = super::solve_stage2(&far_hailstones());
+        assert_eq!(answer.get(), Err(answer::Overflow));
+    }
+    #[cfg(not(any(feature = "checked", feature = "bigint")))]
+    #[test]
+    #[should_panic(expected = "arithmetic overflow")]
+    fn test_stage2_precision() {
+        super::solve_stage2(&far_hailstones());
+    }
 }
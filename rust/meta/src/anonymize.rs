@@ -0,0 +1,362 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    panic,
+};
+
+use aoc_traits::AdventOfCodeDay;
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Days that [`anonymize`] knows how to rewrite.
+pub const ANONYMIZABLE_DAYS: &[usize] = &[8, 19, 20, 24, 25];
+
+/// A rewritten input together with the answers of the original and the rewritten input.
+pub struct Anonymized {
+    pub input: String,
+    pub original: (String, String),
+    pub rewritten: (String, String),
+}
+
+/// Solves both parts of `input`. The solvers panic on inputs they cannot handle, like a day 8
+/// network without `AAA`, which is reported as an error instead.
+fn answers<'a, D: AdventOfCodeDay<'a>>(input: &'a str) -> Result<(String, String)>
+where
+    D::Part1Output: Display,
+    D::Part2Output: Display,
+{
+    panic::catch_unwind(|| {
+        let parsed = D::parse_input(input.trim_end());
+        (
+            D::solve_part1(&parsed).to_string(),
+            D::solve_part2(&parsed).to_string(),
+        )
+    })
+    .map_err(|_| eyre!("The solver failed, both parts have to be solvable for the input"))
+}
+
+/// Hands out fresh names for labels, always mapping the same label to the same name.
+#[derive(Default)]
+struct Renamer {
+    names: HashMap<String, String>,
+    taken: HashSet<String>,
+}
+
+impl Renamer {
+    /// Creates a renamer that leaves the `fixed` labels untouched.
+    fn keeping(fixed: &[&str]) -> Renamer {
+        let mut renamer = Renamer::default();
+        for &label in fixed {
+            renamer.names.insert(label.to_owned(), label.to_owned());
+            renamer.taken.insert(label.to_owned());
+        }
+        renamer
+    }
+
+    fn rename(&mut self, label: &str, mut generate: impl FnMut() -> String) -> Result<String> {
+        if let Some(name) = self.names.get(label) {
+            return Ok(name.clone());
+        }
+        // the name space of a day can be exhausted by large generated inputs
+        for _ in 0..10_000 {
+            let name = generate();
+            if self.taken.insert(name.clone()) {
+                self.names.insert(label.to_owned(), name.clone());
+                return Ok(name);
+            }
+        }
+        Err(eyre!("Ran out of fresh names while renaming {}", label))
+    }
+}
+
+/// Replaces every alphanumeric word of `input`, passing along the character following it.
+fn rename_words(
+    input: &str,
+    mut rename: impl FnMut(&str, Option<char>) -> Result<String>,
+) -> Result<String> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric()) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len());
+        out.push_str(&rename(&rest[..end], rest[end..].chars().next())?);
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn random_word(rng: &mut StdRng, len: usize, letters: &[u8]) -> String {
+    (0..len)
+        .map(|_| letters[rng.gen_range(0..letters.len())] as char)
+        .collect()
+}
+
+const LOWERCASE: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Renames the nodes of the network, keeping `AAA`, `ZZZ` and the meaning of the `A`/`Z` suffixes.
+fn anonymize_day08(input: &str, rng: &mut StdRng) -> Result<String> {
+    let (steps, network) = input
+        .split_once('\n')
+        .ok_or_else(|| eyre!("Missing network"))?;
+    let mut renamer = Renamer::keeping(&["AAA", "ZZZ"]);
+    let network = rename_words(network, |label, _| {
+        let suffix = label.chars().last().unwrap_or_default();
+        renamer.rename(label, || {
+            let mut name = random_word(rng, label.len().saturating_sub(1), UPPERCASE);
+            name.push(match suffix {
+                'A' | 'Z' => suffix,
+                _ => random_word(rng, 1, b"BCDEFGHIJKLMNOPQRSTUVWXY").remove(0),
+            });
+            name
+        })
+    })?;
+    Ok(format!("{}\n{}", steps, network))
+}
+
+/// Permutes the workflow names, keeping `in` and the `A`/`R` targets.
+fn anonymize_day19(input: &str, rng: &mut StdRng) -> Result<String> {
+    let (workflows, parts) = input
+        .split_once("\n\n")
+        .ok_or_else(|| eyre!("Missing parts"))?;
+    let mut renamer = Renamer::keeping(&["in", "A", "R"]);
+    let workflows = rename_words(workflows, |word, next| {
+        // categories are followed by a comparison, ratings are numbers
+        if matches!(next, Some('<' | '>')) || word.bytes().all(|b| b.is_ascii_digit()) {
+            return Ok(word.to_owned());
+        }
+        renamer.rename(word, || random_word(rng, word.len(), LOWERCASE))
+    })?;
+    Ok(format!("{}\n\n{}", workflows, parts))
+}
+
+/// Renames all modules except the `broadcaster` and the final `rx` module.
+fn anonymize_day20(input: &str, rng: &mut StdRng) -> Result<String> {
+    let mut renamer = Renamer::keeping(&["broadcaster", "rx"]);
+    rename_words(input, |word, _| {
+        renamer.rename(word, || random_word(rng, word.len(), LOWERCASE))
+    })
+}
+
+/// Translates all hailstones by the same random offset and adds the same random drift to their
+/// velocities. The rock is moved by the offset and gains the drift as well, as it still hits
+/// every hailstone at the same time.
+fn anonymize_day24(input: &str, rng: &mut StdRng) -> Result<(String, i128)> {
+    let parse = |coordinates: &str, line: &str| -> Result<[i64; 3]> {
+        let coordinates = coordinates
+            .split(',')
+            .map(|x| Ok(x.trim().parse::<i64>()?))
+            .collect::<Result<Vec<_>>>()?;
+        coordinates
+            .try_into()
+            .map_err(|_| eyre!("Invalid hailstone: {}", line))
+    };
+    let hailstones = input
+        .lines()
+        .map(|line| {
+            let (pos, vel) = line
+                .split_once(" @ ")
+                .ok_or_else(|| eyre!("Invalid hailstone: {}", line))?;
+            Ok((parse(pos, line)?, parse(vel, line)?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let offset: [i64; 3] = [(); 3].map(|_| rng.gen_range(-1_000_000_000_000..=1_000_000_000_000));
+    // the solver rejects hailstones that stand still along an axis
+    let drift = (0..100)
+        .map(|_| [(); 3].map(|_| rng.gen_range(-50..=50)))
+        .find(|drift: &[i64; 3]| {
+            hailstones
+                .iter()
+                .all(|(_, vel)| (0..3).all(|i| vel[i] + drift[i] != 0))
+        })
+        .ok_or_else(|| eyre!("Found no drift that keeps every velocity nonzero"))?;
+
+    let mut out = String::with_capacity(input.len());
+    for (pos, vel) in &hailstones {
+        let pos = [0, 1, 2].map(|i| pos[i] + offset[i]);
+        let vel = [0, 1, 2].map(|i| vel[i] + drift[i]);
+        out.push_str(&format!(
+            "{}, {}, {} @ {}, {}, {}\n",
+            pos[0], pos[1], pos[2], vel[0], vel[1], vel[2]
+        ));
+    }
+    Ok((out, offset.iter().map(|&o| i128::from(o)).sum()))
+}
+
+/// Renames all components of the wiring diagram.
+fn anonymize_day25(input: &str, rng: &mut StdRng) -> Result<String> {
+    let mut renamer = Renamer::default();
+    rename_words(input, |word, _| {
+        renamer.rename(word, || random_word(rng, word.len(), LOWERCASE))
+    })
+}
+
+/// Rewrites the input of `day` into a structurally equivalent one that can be shared in bug
+/// reports, and checks that it still produces the expected answers.
+pub fn anonymize(day: usize, input: &str, seed: u64) -> Result<Anonymized> {
+    let rng = &mut StdRng::seed_from_u64(seed);
+    let input = input.trim_end();
+    let solve = |input: &str| match day {
+        8 => answers::<day08::Day8Solver>(input),
+        19 => answers::<day19::Day19Solver>(input),
+        20 => answers::<day20::Day20Solver>(input),
+        24 => answers::<day24::Day24Solver>(input),
+        25 => answers::<day25::Day25Solver>(input),
+        _ => Err(eyre!("Day {} cannot be anonymized", day)),
+    };
+    let original = solve(input)?;
+
+    let (rewritten, expected) = match day {
+        8 => (anonymize_day08(input, rng)?, original.clone()),
+        19 => (anonymize_day19(input, rng)?, original.clone()),
+        20 => (anonymize_day20(input, rng)?, original.clone()),
+        24 => {
+            let (rewritten, shift) = anonymize_day24(input, rng)?;
+            // the hailstones inside the test area change, so part 1 is recomputed
+            let part1 = solve(&rewritten)?.0;
+            let rock = original.1.parse::<i128>()?;
+            (rewritten, (part1, (rock + shift).to_string()))
+        }
+        25 => (anonymize_day25(input, rng)?, original.clone()),
+        _ => unreachable!(),
+    };
+
+    let answers = solve(&rewritten)?;
+    if answers != expected {
+        bail!(
+            "Anonymized input of day {} produces {:?} instead of {:?}",
+            day,
+            answers,
+            expected
+        );
+    }
+
+    Ok(Anonymized {
+        input: rewritten,
+        original,
+        rewritten: answers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    const DAY08: &str = "LLR
+
+AAA = (BBB, BBB)
+BBB = (AAA, ZZZ)
+ZZZ = (ZZZ, ZZZ)";
+    const DAY19: &str = "px{a<2006:qkq,m>2090:A,rfg}
+pv{a>1716:R,A}
+lnx{m>1548:A,A}
+rfg{s<537:gd,x>2440:R,A}
+qs{s>3448:A,lnx}
+qkq{x<1416:A,crn}
+crn{x>2662:A,R}
+in{s<1351:px,qqz}
+qqz{s>2770:qs,m<1801:hdj,R}
+gd{a>3333:R,R}
+hdj{m>838:A,pv}
+
+{x=787,m=2655,a=1222,s=2876}
+{x=1679,m=44,a=2067,s=496}
+{x=2036,m=264,a=79,s=2244}
+{x=2461,m=1339,a=466,s=291}
+{x=2127,m=1623,a=2188,s=1013}";
+    const DAY20: &str = "broadcaster -> a, c
+%a -> b
+%b -> inva
+&inva -> con
+%c -> invc
+&invc -> con
+&con -> rx";
+    const DAY24: &str = "19, 13, 30 @ -2, 1, -2
+18, 19, 22 @ -1, -1, -2
+20, 25, 34 @ -2, -2, -4
+12, 31, 28 @ -1, -2, -1
+20, 19, 15 @ 1, -5, -3";
+    const DAY25: &str = "jqt: rhn xhk nvd
+rsh: frs pzl lsr
+xhk: hfx
+cmg: qnr nvd lhk bvb
+rhn: xhk bvb hfx
+bvb: xhk hfx
+pzl: lsr hfx nvd
+qnr: nvd
+ntq: jqt hfx bvb xhk
+nvd: lhk
+lsr: lhk
+rzs: qnr cmg lsr rsh
+frs: qnr lhk lsr";
+
+    #[test]
+    fn test_anonymize() {
+        let day08 = super::anonymize(8, DAY08, 42).unwrap();
+        assert_eq!(day08.rewritten, (6.to_string(), 6.to_string()));
+        assert!(day08.input.starts_with("LLR\n\nAAA = ("));
+        assert!(day08.input.contains(", ZZZ)"));
+        assert!(!day08.input.contains("BBB"));
+        // the same seed renames the same way
+        assert_eq!(super::anonymize(8, DAY08, 42).unwrap().input, day08.input);
+
+        let day19 = super::anonymize(19, DAY19, 42).unwrap();
+        assert_eq!(day19.original, day19.rewritten);
+        assert!(day19.input.contains("\nin{s<1351:"));
+        assert!(day19.input.ends_with(&DAY19[DAY19.find("\n\n").unwrap()..]));
+
+        let day20 = super::anonymize(20, DAY20, 42).unwrap();
+        assert_eq!(day20.original, day20.rewritten);
+        assert_eq!(day20.original.1, "4");
+        assert!(day20.input.starts_with("broadcaster -> "));
+        assert!(day20.input.ends_with(" -> rx"));
+        assert!(!day20.input.contains("inva"));
+
+        let day25 = super::anonymize(25, DAY25, 42).unwrap();
+        assert_eq!(day25.rewritten.0, "54");
+        assert!(!day25.input.contains("jqt"));
+
+        // every hailstone moves by the same offset and drift, the rock moves by the offset
+        let day24 = super::anonymize(24, DAY24, 42).unwrap();
+        let hailstones = |input: &str| -> Vec<Vec<i64>> {
+            input
+                .lines()
+                .map(|line| {
+                    line.split([',', '@'])
+                        .map(|x| x.trim().parse().unwrap())
+                        .collect()
+                })
+                .collect()
+        };
+        let shifts: Vec<Vec<i64>> = hailstones(&day24.input)
+            .iter()
+            .zip(hailstones(DAY24))
+            .map(|(moved, original)| moved.iter().zip(original).map(|(m, o)| m - o).collect())
+            .collect();
+        assert!(shifts.iter().all(|shift| *shift == shifts[0]));
+        let (offset, drift) = shifts[0].split_at(3);
+        assert_ne!(drift, [0; 3]);
+        let offset: i128 = offset.iter().map(|&o| i128::from(o)).sum();
+        assert_eq!(day24.original.1, "47");
+        assert_eq!(day24.rewritten.1, (47 + offset).to_string());
+    }
+
+    #[test]
+    fn test_anonymize_errors() {
+        assert!(super::anonymize(7, DAY08, 42).is_err());
+        // the part 2 example has no `AAA`, so part 1 cannot be solved
+        let ghosts = "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+XXX = (XXX, XXX)";
+        assert!(super::anonymize(8, ghosts, 42).is_err());
+    }
+}
@@ -1,4 +1,14 @@
+use std::{fmt::Display, io::BufRead};
+
 use aoc_traits::AdventOfCodeSolutions;
+use color_eyre::{eyre::eyre, Result};
+
+mod anonymize;
+mod registry;
+mod report;
+pub use anonymize::{anonymize, Anonymized, ANONYMIZABLE_DAYS};
+pub use registry::{year, Year, YEARS};
+pub use report::{format_table, AllocStats, CountingAllocator, DayReport, Measurement};
 
 pub struct AoC2023;
 
@@ -53,3 +63,24 @@ impl AdventOfCodeSolutions for AoC2023 {
 
     type Day25 = day25::Day25Solver;
 }
+
+/// Days whose solvers can fold their answers line by line, see [`solve_day_streaming`].
+pub const STREAMING_DAYS: &[usize] = &[1, 2, 4, 6, 9, 12, 15];
+
+/// Solves `day` of 2023 by reading `input` incrementally instead of loading it into memory first.
+/// Only the days listed in [`STREAMING_DAYS`] are supported.
+pub fn solve_day_streaming<R: BufRead>(day: usize, input: R) -> Result<(String, String)> {
+    fn show<A: Display, B: Display>((a, b): (A, B)) -> (String, String) {
+        (a.to_string(), b.to_string())
+    }
+    match day {
+        1 => day01::solve_streaming(input, day01::NoDigitPolicy::default()).map(show),
+        2 => day02::solve_streaming(input, &day02::Cubes::standard_bag()).map(show),
+        4 => day04::solve_streaming(input).map(show),
+        6 => day06::solve_streaming(input).map(show),
+        9 => day09::solve_streaming(input).map(show),
+        12 => day12::solve_streaming(input).map(show),
+        15 => day15::solve_streaming(input).map(show),
+        _ => Err(eyre!("Day {} does not support streaming input", day)),
+    }
+}
@@ -1,23 +1,138 @@
-use std::path::PathBuf;
+use std::{fs::File, io::BufReader, path::PathBuf};
 
-use aoc_traits::AdventOfCodeSolutions;
-use clap::Parser;
-use color_eyre::Result;
+use clap::{Args, Parser, Subcommand};
+use color_eyre::{eyre::bail, Result};
 
 #[derive(Parser)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct AoCRunner {
+    #[clap(subcommand)]
+    command: Option<Command>,
+    #[clap(flatten)]
+    run: Option<RunArgs>,
+}
+
+#[derive(Args)]
+struct RunArgs {
+    #[clap(short, long, default_value_t = 2023)]
+    year: u32,
     #[clap(short, long)]
     day: usize,
     #[clap(short, long)]
     input: PathBuf,
+    /// Read the input line by line instead of loading it into memory first
+    #[clap(short, long)]
+    stream: bool,
+    /// How day 1 handles lines without any digit: skip, error or zero
+    #[clap(long)]
+    no_digit: Option<day01::NoDigitPolicy>,
 }
 
-fn main() -> Result<()> {
-    let args = AoCRunner::parse();
+#[derive(Subcommand)]
+enum Command {
+    /// Rewrite a 2023 input into an equivalent one that can be shared in bug reports
+    Anonymize {
+        #[clap(short, long)]
+        day: usize,
+        #[clap(short, long)]
+        input: PathBuf,
+        /// Where to write the rewritten input, stdout if not given
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+        /// Seed for the random renaming, random if not given
+        #[clap(long)]
+        seed: Option<u64>,
+    },
+    /// Run every day of a year and print a table of timings and heap usage
+    All {
+        #[clap(short, long, default_value_t = 2023)]
+        year: u32,
+        /// Directory containing a dayNN/input.txt for every day
+        #[clap(long, default_value = ".")]
+        inputs: PathBuf,
+    },
+}
+
+fn run(args: RunArgs) -> Result<()> {
+    let year = meta::year(args.year)?;
+
+    if let Some(policy) = args.no_digit {
+        if args.year != 2023 || args.day != 1 {
+            bail!("--no-digit only applies to day 1 of 2023");
+        }
+        let (part1, part2) = if args.stream {
+            let input = BufReader::new(File::open(&args.input)?);
+            day01::solve_streaming(input, policy)?
+        } else {
+            day01::solve(&std::fs::read_to_string(&args.input)?, policy)?
+        };
+        println!("Part 1: {}", part1);
+        println!("Part 2: {}", part2);
+        return Ok(());
+    }
+
+    if args.stream {
+        let input = Box::new(BufReader::new(File::open(&args.input)?));
+        let (part1, part2) = year.solve_day_streaming(args.day, input)?;
+        println!("Part 1: {}", part1);
+        println!("Part 2: {}", part2);
+        return Ok(());
+    }
 
     let input = std::fs::read_to_string(&args.input)?;
 
-    meta::AoC2023::solve_day(args.day, &input).map_err(|e| color_eyre::eyre::eyre!(e))?;
+    year.solve_day(args.day, &input)?;
 
     Ok(())
 }
+
+fn anonymize(day: usize, input: PathBuf, output: Option<PathBuf>, seed: Option<u64>) -> Result<()> {
+    let input = std::fs::read_to_string(input)?;
+    let anonymized = meta::anonymize(day, &input, seed.unwrap_or_else(rand::random))?;
+
+    eprintln!(
+        "Part 1: {} -> {}",
+        anonymized.original.0, anonymized.rewritten.0
+    );
+    eprintln!(
+        "Part 2: {} -> {}",
+        anonymized.original.1, anonymized.rewritten.1
+    );
+    match output {
+        Some(output) => std::fs::write(output, anonymized.input + "\n")?,
+        None => println!("{}", anonymized.input),
+    }
+
+    Ok(())
+}
+
+fn run_all(year: u32, inputs: PathBuf) -> Result<()> {
+    let year = meta::year(year)?;
+    let mut reports = Vec::new();
+    for day in 1..=25 {
+        let path = inputs.join(format!("day{:02}", day)).join("input.txt");
+        let Ok(input) = std::fs::read_to_string(&path) else {
+            eprintln!("Skipping day {}, no input at {}", day, path.display());
+            continue;
+        };
+        reports.push((day, year.measure_day(day, &input)?));
+    }
+    print!("{}", meta::format_table(&reports));
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args = AoCRunner::parse();
+
+    match args.command {
+        Some(Command::Anonymize {
+            day,
+            input,
+            output,
+            seed,
+        }) => anonymize(day, input, output, seed),
+        Some(Command::All { year, inputs }) => run_all(year, inputs),
+        None => run(args.run.expect("required unless a subcommand is given")),
+    }
+}
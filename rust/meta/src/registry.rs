@@ -0,0 +1,115 @@
+use std::io::BufRead;
+
+use aoc_traits::AdventOfCodeSolutions;
+use color_eyre::{eyre::eyre, Result};
+
+use crate::{report::measure_day, AoC2023, DayReport};
+
+type SolveFn = fn(usize, &str) -> Result<()>;
+type MeasureFn = fn(usize, &str) -> Result<DayReport>;
+type StreamingFn = fn(usize, Box<dyn BufRead>) -> Result<(String, String)>;
+
+/// The solutions of a single year, as known to the runner.
+pub struct Year {
+    pub year: u32,
+    solve: SolveFn,
+    measure: MeasureFn,
+    solve_streaming: Option<StreamingFn>,
+}
+
+fn solve<S: AdventOfCodeSolutions>(day: usize, input: &str) -> Result<()> {
+    S::solve_day(day, input).map_err(|e| eyre!(e))
+}
+
+fn measure<S: AdventOfCodeSolutions>(day: usize, input: &str) -> Result<DayReport> {
+    match day {
+        1 => Ok(measure_day::<S::Day01>(input)),
+        2 => Ok(measure_day::<S::Day02>(input)),
+        3 => Ok(measure_day::<S::Day03>(input)),
+        4 => Ok(measure_day::<S::Day04>(input)),
+        5 => Ok(measure_day::<S::Day05>(input)),
+        6 => Ok(measure_day::<S::Day06>(input)),
+        7 => Ok(measure_day::<S::Day07>(input)),
+        8 => Ok(measure_day::<S::Day08>(input)),
+        9 => Ok(measure_day::<S::Day09>(input)),
+        10 => Ok(measure_day::<S::Day10>(input)),
+        11 => Ok(measure_day::<S::Day11>(input)),
+        12 => Ok(measure_day::<S::Day12>(input)),
+        13 => Ok(measure_day::<S::Day13>(input)),
+        14 => Ok(measure_day::<S::Day14>(input)),
+        15 => Ok(measure_day::<S::Day15>(input)),
+        16 => Ok(measure_day::<S::Day16>(input)),
+        17 => Ok(measure_day::<S::Day17>(input)),
+        18 => Ok(measure_day::<S::Day18>(input)),
+        19 => Ok(measure_day::<S::Day19>(input)),
+        20 => Ok(measure_day::<S::Day20>(input)),
+        21 => Ok(measure_day::<S::Day21>(input)),
+        22 => Ok(measure_day::<S::Day22>(input)),
+        23 => Ok(measure_day::<S::Day23>(input)),
+        24 => Ok(measure_day::<S::Day24>(input)),
+        25 => Ok(measure_day::<S::Day25>(input)),
+        _ => Err(eyre!("Invalid day {}", day)),
+    }
+}
+
+impl Year {
+    /// Registers the days of `S` as the solutions for `year`.
+    pub const fn new<S: AdventOfCodeSolutions>(year: u32) -> Year {
+        Year {
+            year,
+            solve: solve::<S>,
+            measure: measure::<S>,
+            solve_streaming: None,
+        }
+    }
+
+    /// Adds a solver for inputs that are read incrementally, see [`Year::solve_day_streaming`].
+    pub const fn with_streaming(self, solve_streaming: StreamingFn) -> Year {
+        Year {
+            solve_streaming: Some(solve_streaming),
+            ..self
+        }
+    }
+
+    pub fn solve_day(&self, day: usize, input: &str) -> Result<()> {
+        (self.solve)(day, input)
+    }
+
+    /// Solves `day` like [`Year::solve_day`], but returns the timings and heap usage of each
+    /// stage instead of printing the answers.
+    pub fn measure_day(&self, day: usize, input: &str) -> Result<DayReport> {
+        (self.measure)(day, input)
+    }
+
+    pub fn solve_day_streaming(
+        &self,
+        day: usize,
+        input: Box<dyn BufRead>,
+    ) -> Result<(String, String)> {
+        let solve_streaming = self
+            .solve_streaming
+            .ok_or_else(|| eyre!("Year {} does not support streaming input", self.year))?;
+        solve_streaming(day, input)
+    }
+}
+
+/// All years known to the runner. To add a year, implement [`AdventOfCodeSolutions`] for it
+/// and register it here.
+pub const YEARS: &[Year] = &[Year::new::<AoC2023>(2023).with_streaming(crate::solve_day_streaming)];
+
+pub fn year(year: u32) -> Result<&'static Year> {
+    YEARS
+        .iter()
+        .find(|y| y.year == year)
+        .ok_or_else(|| eyre!("No solutions registered for year {}", year))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_year() {
+        assert_eq!(super::year(2023).unwrap().year, 2023);
+        let error = super::year(1999).err().unwrap();
+        assert_eq!(error.to_string(), "No solutions registered for year 1999");
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Display,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use aoc_traits::AdventOfCodeDay;
+
+static CURRENT_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Wraps the system allocator and keeps track of the current and peak heap usage.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn grow(&self, bytes: usize) {
+        let current = CURRENT_BYTES.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        PEAK_BYTES.fetch_max(current, Ordering::Relaxed);
+    }
+
+    fn shrink(&self, bytes: usize) {
+        CURRENT_BYTES.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            self.grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        self.shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            // only the difference counts, even if the block was moved
+            if new_size > layout.size() {
+                self.grow(new_size - layout.size());
+            } else {
+                self.shrink(layout.size() - new_size);
+            }
+        }
+        new_ptr
+    }
+}
+
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Heap usage of a single stage, only available with the `alloc-stats` feature.
+#[derive(Debug, Clone, Copy)]
+pub struct AllocStats {
+    /// Peak heap usage above the usage at the start of the stage.
+    pub peak_bytes: usize,
+    /// Number of allocations, counting reallocations as well.
+    pub allocations: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Measurement {
+    pub time: Duration,
+    pub alloc: Option<AllocStats>,
+}
+
+/// Timings and heap usage of parsing and solving both parts of a day.
+#[derive(Debug, Clone, Copy)]
+pub struct DayReport {
+    pub parse: Measurement,
+    pub part1: Measurement,
+    pub part2: Measurement,
+}
+
+fn measure<T>(f: impl FnOnce() -> T) -> (T, Measurement) {
+    let start_bytes = CURRENT_BYTES.load(Ordering::Relaxed);
+    let start_allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    PEAK_BYTES.store(start_bytes, Ordering::Relaxed);
+
+    let start = Instant::now();
+    let result = f();
+    let time = start.elapsed();
+
+    let alloc = cfg!(feature = "alloc-stats").then(|| AllocStats {
+        peak_bytes: PEAK_BYTES
+            .load(Ordering::Relaxed)
+            .saturating_sub(start_bytes),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - start_allocations,
+    });
+    (result, Measurement { time, alloc })
+}
+
+pub(crate) fn measure_day<'a, D: AdventOfCodeDay<'a>>(input: &'a str) -> DayReport {
+    let (parsed, parse) = measure(|| D::parse_input(input.trim_end()));
+    let (_, part1) = measure(|| D::solve_part1(&parsed));
+    let (_, part2) = measure(|| D::solve_part2(&parsed));
+    DayReport {
+        parse,
+        part1,
+        part2,
+    }
+}
+
+struct Bytes(usize);
+
+impl Display for Bytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            write!(f, "{} {}", self.0, UNITS[0])
+        } else {
+            write!(f, "{:.1} {}", value, UNITS[unit])
+        }
+    }
+}
+
+impl Measurement {
+    fn cells(&self) -> [String; 3] {
+        let time = format!("{:.2?}", self.time);
+        match self.alloc {
+            Some(alloc) => [
+                time,
+                Bytes(alloc.peak_bytes).to_string(),
+                alloc.allocations.to_string(),
+            ],
+            None => [time, "-".to_owned(), "-".to_owned()],
+        }
+    }
+}
+
+/// Renders the reports of several days as a table with time, peak heap and allocation count
+/// columns for every stage.
+pub fn format_table(reports: &[(usize, DayReport)]) -> String {
+    let mut table = format!(
+        "{:>3} | {:>10} {:>10} {:>9} | {:>10} {:>10} {:>9} | {:>10} {:>10} {:>9}\n",
+        "day", "parse", "peak", "allocs", "part 1", "peak", "allocs", "part 2", "peak", "allocs"
+    );
+    for (day, report) in reports {
+        let [t0, p0, a0] = report.parse.cells();
+        let [t1, p1, a1] = report.part1.cells();
+        let [t2, p2, a2] = report.part2.cells();
+        table.push_str(&format!(
+            "{:>3} | {:>10} {:>10} {:>9} | {:>10} {:>10} {:>9} | {:>10} {:>10} {:>9}\n",
+            day, t0, p0, a0, t1, p1, a1, t2, p2, a2
+        ));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_format_table() {
+        use std::time::Duration;
+
+        use super::{AllocStats, DayReport, Measurement};
+
+        let measurement = |micros, peak_bytes| Measurement {
+            time: Duration::from_micros(micros),
+            alloc: Some(AllocStats {
+                peak_bytes,
+                allocations: 7,
+            }),
+        };
+        let report = DayReport {
+            parse: measurement(1500, 1023),
+            part1: measurement(20, 1536),
+            part2: Measurement {
+                time: Duration::from_secs(2),
+                alloc: None,
+            },
+        };
+        let table = super::format_table(&[(3, report)]);
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("day |      parse       peak    allocs |"));
+        assert_eq!(
+            lines[1],
+            "  3 |     1.50ms     1023 B         7 |    20.00µs    1.5 KiB         7 |      \
+             2.00s          -         -"
+        );
+
+        // units roll over at 1024 and stop at GiB
+        let peaks = [
+            (1 << 20, "1.0 MiB"),
+            (5 << 30, "5.0 GiB"),
+            (3 << 40, "3072.0 GiB"),
+        ];
+        for (peak_bytes, expected) in peaks {
+            let report = DayReport {
+                part1: measurement(1, peak_bytes),
+                ..report
+            };
+            let table = super::format_table(&[(1, report)]);
+            assert!(table.contains(expected), "{}", table);
+        }
+    }
+
+    // with `alloc-stats` every allocation of the other tests is counted as well
+    #[cfg(not(feature = "alloc-stats"))]
+    #[test]
+    fn test_realloc() {
+        use std::{
+            alloc::{GlobalAlloc, Layout},
+            sync::atomic::Ordering,
+        };
+
+        use super::{CountingAllocator, CURRENT_BYTES, PEAK_BYTES};
+
+        let layout = Layout::from_size_align(1024, 8).unwrap();
+        unsafe {
+            let ptr = CountingAllocator.alloc(layout);
+            let start = CURRENT_BYTES.load(Ordering::Relaxed);
+            PEAK_BYTES.store(start, Ordering::Relaxed);
+            let ptr = CountingAllocator.realloc(ptr, layout, 4096);
+            assert_eq!(CURRENT_BYTES.load(Ordering::Relaxed), start + 3072);
+            assert_eq!(PEAK_BYTES.load(Ordering::Relaxed), start + 3072);
+
+            let layout = Layout::from_size_align(4096, 8).unwrap();
+            let ptr = CountingAllocator.realloc(ptr, layout, 512);
+            assert_eq!(CURRENT_BYTES.load(Ordering::Relaxed), start - 512);
+            assert_eq!(PEAK_BYTES.load(Ordering::Relaxed), start + 3072);
+            CountingAllocator.dealloc(ptr, Layout::from_size_align(512, 8).unwrap());
+        }
+    }
+}